@@ -0,0 +1,43 @@
+// Benchmarks for the days that ship one, run on generated input in release mode:
+//
+//   cargo run --release --bin day01 -- --bench 16
+//
+// The number after the flag is the size of the generated input, its unit is up to the day.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const RUNS: usize = 5;
+
+// returns the requested size if `--bench [size]` is given
+pub fn from_args(default: usize) -> Option<usize> {
+    let args = std::env::args().collect::<Vec<_>>();
+    let idx = args.iter().position(|arg| arg == "--bench")?;
+    Some(
+        args.get(idx + 1)
+            .and_then(|a| a.parse().ok())
+            .unwrap_or(default),
+    )
+}
+
+// runs `f` a few times and prints the best time, with the throughput if `bytes` is given
+pub fn measure<T, F: FnMut() -> T>(label: &str, bytes: Option<usize>, mut f: F) -> Duration {
+    let best = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(f());
+            start.elapsed()
+        })
+        .min()
+        .unwrap();
+    match bytes {
+        Some(bytes) => println!(
+            "{}: {:?} ({:.1} MB/s)",
+            label,
+            best,
+            bytes as f64 / best.as_secs_f64() / 1_000_000.0
+        ),
+        None => println!("{}: {:?}", label, best),
+    }
+    best
+}
@@ -0,0 +1,196 @@
+// Runs the registered days of a year for one profile and checks them against the stored answers:
+//
+//   cargo build --release --bins
+//   cargo run --release --bin aoc -- 2023 [day...] [--profile <name>] [--save]
+//
+// `--save` records the answers of parts that have none stored yet. Flags and days can come in any
+// order after the year.
+
+use advent_of_code_2023::workspace::{self, Entry, Puzzle, PROFILE_VAR};
+use std::path::PathBuf;
+use std::process::{Command, ExitCode};
+
+const USAGE: &str = "usage: aoc <year> [day...] [--profile <name>] [--save]";
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    year: u16,
+    days: Vec<u8>,
+    profile: Option<String>,
+    save: bool,
+}
+
+// the flags are taken with their values first, everything else has to be a day
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut args = args.iter();
+    let year = args.next().ok_or("Missing year")?;
+    let mut options = Options {
+        year: year
+            .parse()
+            .map_err(|_| format!("Invalid year: {}", year))?,
+        days: Vec::new(),
+        profile: None,
+        save: false,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--save" => options.save = true,
+            "--profile" => {
+                let profile = args.next().ok_or("Missing value for --profile")?;
+                options.profile = Some(profile.clone());
+            }
+            _ if arg.starts_with("--") => return Err(format!("Unknown flag: {}", arg)),
+            _ => options
+                .days
+                .push(arg.parse().map_err(|_| format!("Invalid day: {}", arg))?),
+        }
+    }
+    Ok(options)
+}
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let Options {
+        year,
+        days,
+        profile,
+        save,
+    } = match parse_args(&args) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}\n{}", error, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    let profile = profile.unwrap_or_else(workspace::profile);
+
+    let entries = if days.is_empty() {
+        workspace::days(year).collect::<Vec<_>>()
+    } else {
+        days.iter()
+            .filter_map(|&day| {
+                let entry = workspace::find(year, day);
+                if entry.is_none() {
+                    eprintln!("{} day {} is not registered", year, day);
+                }
+                entry
+            })
+            .collect()
+    };
+
+    let mut ok = true;
+    for entry in entries {
+        ok &= run(entry, &profile, save);
+    }
+    if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+// the days are built as sibling binaries of the runner
+fn binary_path(entry: &Entry) -> PathBuf {
+    let exe = std::env::current_exe().unwrap();
+    exe.with_file_name(format!("{}{}", entry.binary, std::env::consts::EXE_SUFFIX))
+}
+
+fn run(entry: &Entry, profile: &str, save: bool) -> bool {
+    let puzzle = Puzzle::new(entry.year, entry.day, profile);
+    let name = format!("{} day {:02} ({})", puzzle.year, puzzle.day, profile);
+    if !PathBuf::from(puzzle.input()).exists() {
+        println!("{}: no input at {}", name, puzzle.input());
+        return true;
+    }
+    let binary = binary_path(entry);
+    if !binary.exists() {
+        println!(
+            "{}: {} is not built, run `cargo build --bins`",
+            name, entry.binary
+        );
+        return false;
+    }
+
+    let output = Command::new(&binary)
+        .env(PROFILE_VAR, profile)
+        .output()
+        .unwrap();
+    if !output.status.success() {
+        println!("{}: {}", name, output.status);
+        print!("{}", String::from_utf8_lossy(&output.stderr));
+        return false;
+    }
+
+    let answers = workspace::parse_answers(&String::from_utf8_lossy(&output.stdout));
+    let mut stored = puzzle.answers().unwrap();
+    let mut ok = true;
+    let mut changed = false;
+    for (part, value) in &answers {
+        let status = match stored.get(part) {
+            Some(expected) if expected == value => "ok".to_string(),
+            Some(expected) => {
+                ok = false;
+                format!("expected {}", expected)
+            }
+            None if save => {
+                stored.insert(*part, value.clone());
+                changed = true;
+                "saved".to_string()
+            }
+            None => "unchecked".to_string(),
+        };
+        println!("{} part {}: {} ({})", name, part, value, status);
+    }
+    if changed {
+        puzzle.save_answers(&stored).unwrap();
+    }
+    ok
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Options, String> {
+        let args = args
+            .split_whitespace()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        parse_args(&args)
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = Options {
+            year: 2023,
+            days: vec![5, 6],
+            profile: Some("alt".to_string()),
+            save: false,
+        };
+        assert_eq!(parse("2023 --profile alt 5 6"), Ok(options));
+        assert_eq!(
+            parse("2023 5 --profile alt 6").map(|o| o.days),
+            Ok(vec![5, 6])
+        );
+        let options = parse("2023 --save 7").unwrap();
+        assert!(options.save);
+        assert_eq!((options.days, options.profile), (vec![7], None));
+        assert_eq!(parse("2023").map(|o| o.days), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        assert_eq!(parse(""), Err("Missing year".to_string()));
+        assert_eq!(parse("twenty"), Err("Invalid year: twenty".to_string()));
+        assert_eq!(parse("2023 5 x"), Err("Invalid day: x".to_string()));
+        assert_eq!(parse("2023 300"), Err("Invalid day: 300".to_string()));
+        assert_eq!(
+            parse("2023 --profile"),
+            Err("Missing value for --profile".to_string())
+        );
+        assert_eq!(
+            parse("2023 --verbose 5"),
+            Err("Unknown flag: --verbose".to_string())
+        );
+    }
+}
@@ -1,21 +1,54 @@
 // --- Day 1: Trebuchet?! ---
-use advent_of_code_2023::read_lines;
+use advent_of_code_2023::{bench, fold_lines, fuzz, workspace::Puzzle, LineReader};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use regex::Regex;
+use std::collections::VecDeque;
+use std::io::{self, Write};
 
 fn main() {
-    println!("Part 1: {}", part_1("src/bin/day01/input.txt"));
-    println!("Part 2: {}", part_2("src/bin/day01/input.txt"));
-}
+    if fuzz::from_args("day01", fuzz_target) {
+        return;
+    }
+    if let Some(megabytes) = bench::from_args(16) {
+        bench(megabytes);
+        return;
+    }
 
-fn part_1(filename: &str) -> i32 {
-    let lines = read_lines(filename).unwrap();
-    let mut total = 0;
-    for line in &lines {
-        if let Some(number) = get_line_number_1(line) {
-            total += number;
+    let input = Puzzle::from_env(2023, 1).input();
+    let args = std::env::args().collect::<Vec<_>>();
+    let vocabulary = args
+        .iter()
+        .position(|arg| arg == "--vocabulary")
+        .and_then(|idx| args.get(idx + 1))
+        .map_or("english", |v| v.as_str());
+    let vocabulary = match DigitVocabulary::new(vocabulary) {
+        Ok(vocabulary) => vocabulary,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
         }
+    };
+    if args.iter().any(|arg| arg == "--explain") {
+        explain(&input, &vocabulary, io::stdout().lock()).unwrap();
+        return;
     }
-    total
+    println!("Part 1: {}", part_1(&input));
+    println!("Part 2: {}", part_2(&input, &vocabulary));
+}
+
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        part_1(filename);
+        part_2(filename, &DigitVocabulary::default());
+    });
+}
+
+fn part_1(filename: &str) -> i32 {
+    fold_lines(filename, 0, |total, line| {
+        total + get_line_number_1(line).unwrap_or(0)
+    })
+    .unwrap()
 }
 
 fn get_line_number_1(line: &str) -> Option<i32> {
@@ -32,67 +65,374 @@ fn get_line_number_1(line: &str) -> Option<i32> {
     })
 }
 
-fn part_2(filename: &str) -> i32 {
-    let lines = read_lines(filename).unwrap();
-    let mut total = 0;
-    let patterns = vec![
-        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "1", "2", "3", "4",
-        "5", "6", "7", "8", "9",
-    ];
-    let regexes: Vec<Regex> = patterns.iter().map(|x| Regex::new(x).unwrap()).collect();
-
-    for line in &lines {
-        if let Some(number) = get_line_number_2(line, &regexes) {
-            total += number;
+fn part_2(filename: &str, vocabulary: &DigitVocabulary) -> i32 {
+    let matcher = DigitMatcher::new(&vocabulary.tokens);
+
+    fold_lines(filename, 0, |total, line| {
+        total + get_line_number_2(line, &matcher).unwrap_or(0)
+    })
+    .unwrap()
+}
+
+// digit words from zero to nine, an empty word means the language has none
+const ENGLISH: [&str; 10] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+const GERMAN: [&str; 10] = [
+    "null", "eins", "zwei", "drei", "vier", "fünf", "sechs", "sieben", "acht", "neun",
+];
+const FRENCH: [&str; 10] = [
+    "zéro", "un", "deux", "trois", "quatre", "cinq", "six", "sept", "huit", "neuf",
+];
+const ROMAN: [&str; 10] = ["", "i", "ii", "iii", "iv", "v", "vi", "vii", "viii", "ix"];
+
+struct DigitVocabulary {
+    tokens: Vec<(String, u32)>,
+}
+
+impl DigitVocabulary {
+    // Create a vocabulary from a config like: german zero, french roman, english uno=1 dos=2
+    // The digits are always part of it, zero (the digit and the words) only with `zero`.
+    pub fn new(config: &str) -> Result<DigitVocabulary, String> {
+        let mut languages = Vec::new();
+        let mut zero = false;
+        let mut tokens = Vec::new();
+        for item in config.split([' ', ',']).filter(|item| !item.is_empty()) {
+            match item.to_lowercase().as_str() {
+                "english" => languages.push(ENGLISH),
+                "german" => languages.push(GERMAN),
+                "french" => languages.push(FRENCH),
+                "roman" => languages.push(ROMAN),
+                "zero" => zero = true,
+                _ => match item.split_once('=') {
+                    Some((word, value)) => match value.parse::<u32>() {
+                        Ok(value) if value < 10 => tokens.push((word.to_string(), value)),
+                        _ => return Err(format!("Invalid digit value: {}", item)),
+                    },
+                    None => return Err(format!("Unknown vocabulary: {}", item)),
+                },
+            }
+        }
+
+        let first = if zero { 0 } else { 1 };
+        for value in first..10 {
+            tokens.push((value.to_string(), value));
+            for words in &languages {
+                if !words[value as usize].is_empty() {
+                    tokens.push((words[value as usize].to_string(), value));
+                }
+            }
+        }
+
+        Ok(DigitVocabulary { tokens })
+    }
+}
+
+// the puzzle's vocabulary, english without zero
+impl Default for DigitVocabulary {
+    fn default() -> Self {
+        DigitVocabulary::new("english").unwrap()
+    }
+}
+
+/// Returns the number made of the first and last digit token in the line, or `None` if the line
+/// has no digit tokens. Tokens may overlap, so "oneight" gives 18.
+fn get_line_number_2(line: &str, matcher: &DigitMatcher) -> Option<i32> {
+    matcher
+        .first_last(line)
+        .map(|(first, last)| (first.value * 10 + last.value) as i32)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Token {
+    start: usize,
+    len: usize,
+    value: u32,
+}
+
+// finds the first token with one forward scan and the last one with one backward scan
+struct DigitMatcher {
+    forward: Automaton,
+    backward: Automaton,
+}
+
+impl DigitMatcher {
+    // tokens are matched case-insensitively, so they are folded to lowercase up front
+    fn new<S: AsRef<str>>(tokens: &[(S, u32)]) -> Self {
+        let tokens = tokens
+            .iter()
+            .map(|(t, v)| (t.as_ref().to_lowercase(), *v))
+            .collect::<Vec<_>>();
+        let forward = tokens.iter().map(|(t, v)| (t.bytes().collect(), *v));
+        let backward = tokens.iter().map(|(t, v)| (t.bytes().rev().collect(), *v));
+        DigitMatcher {
+            forward: Automaton::new(forward),
+            backward: Automaton::new(backward),
         }
     }
-    total
-}
-
-/// This function searches for occurrences of regular expressions in a given line.
-/// It returns the i32 number corresponding to the first and last match found, if any.
-///
-/// # Arguments
-///
-/// * `line` - A string slice representing the line to search for matches.
-/// * `regexes` - A vector of regular expressions to use for matching, in our case the words for
-/// numbers from 1 to 9 and the digits 1 to 9.
-///
-/// # Returns
-///
-/// An optional integer representing the line number, or `None` if no matches are found.
-/// ```
-fn get_line_number_2(line: &str, regexes: &Vec<Regex>) -> Option<i32> {
-    let mut matches: Vec<(i32, &str)> = Vec::new();
-    for re in regexes {
-        for mat in re.find_iter(line) {
-            matches.push((mat.start() as i32, &mat.as_str()));
+
+    fn first_last(&self, line: &str) -> Option<(Token, Token)> {
+        if line.is_ascii() {
+            let bytes = line.as_bytes();
+            let first = self.forward.leftmost(bytes.iter().copied())?;
+            let last = self.backward.leftmost(bytes.iter().rev().copied())?;
+            // the backward scan counts from the end of the line
+            let last = Token {
+                start: bytes.len() - last.start - last.len,
+                ..last
+            };
+            return Some((first, last));
         }
+
+        // folding can change the length of a character, so every folded byte keeps the span of
+        // the character it came from
+        let folded = line
+            .char_indices()
+            .flat_map(|(start, c)| {
+                let end = start + c.len_utf8();
+                c.to_lowercase().flat_map(move |lower| {
+                    let mut buf = [0; 4];
+                    let len = lower.encode_utf8(&mut buf).len();
+                    buf.into_iter().take(len).map(move |b| (b, start, end))
+                })
+            })
+            .collect::<Vec<_>>();
+        let span = |from: usize, to: usize, value: u32| Token {
+            start: folded[from].1,
+            len: folded[to].2 - folded[from].1,
+            value,
+        };
+        let first = self.forward.leftmost(folded.iter().map(|f| f.0))?;
+        let last = self.backward.leftmost(folded.iter().rev().map(|f| f.0))?;
+        let last_from = folded.len() - last.start - last.len;
+        Some((
+            span(first.start, first.start + first.len - 1, first.value),
+            span(last_from, last_from + last.len - 1, last.value),
+        ))
     }
-    if matches.len() > 0 {
-        matches.sort_by(|a, b| a.0.cmp(&b.0));
-        let number = match_number(String::new(), matches[0].1);
-        let number = match_number(number, matches[matches.len() - 1].1);
-        Some(number.parse::<i32>().unwrap())
+}
+
+// Aho–Corasick automaton compiled to a DFA over bytes, so each byte is a single table lookup.
+// Every state keeps the longest token ending in it, which is the one that starts the earliest.
+struct Automaton {
+    next: Vec<[u32; 256]>,
+    output: Vec<Option<(usize, u32)>>,
+    max_len: usize,
+}
+
+impl Automaton {
+    fn new(tokens: impl Iterator<Item = (Vec<u8>, u32)>) -> Self {
+        // build the trie, state 0 is the root so 0 also marks a missing child
+        let mut next = vec![[0u32; 256]];
+        let mut output = vec![None];
+        let mut max_len = 0;
+        for (token, value) in tokens {
+            let mut state = 0;
+            for &b in &token {
+                if next[state][b as usize] == 0 {
+                    next.push([0; 256]);
+                    output.push(None);
+                    next[state][b as usize] = (next.len() - 1) as u32;
+                }
+                state = next[state][b as usize] as usize;
+            }
+            if output[state].is_none_or(|(len, _)| token.len() > len) {
+                output[state] = Some((token.len(), value));
+            }
+            max_len = max_len.max(token.len());
+        }
+
+        // fill in the failure transitions breadth first, so a state's failure target is complete
+        // before the state itself is visited
+        let mut fail = vec![0usize; next.len()];
+        let mut queue = next[0]
+            .iter()
+            .filter(|&&child| child != 0)
+            .map(|&child| child as usize)
+            .collect::<VecDeque<_>>();
+        while let Some(state) = queue.pop_front() {
+            let inherited = output[fail[state]];
+            if output[state].is_none() {
+                output[state] = inherited;
+            }
+            let fallback = next[fail[state]];
+            for (b, slot) in next[state].iter_mut().enumerate() {
+                if *slot != 0 {
+                    fail[*slot as usize] = fallback[b] as usize;
+                    queue.push_back(*slot as usize);
+                } else {
+                    *slot = fallback[b];
+                }
+            }
+        }
+
+        // the tokens are lowercase, so ASCII uppercase can take the same transitions
+        for state in next.iter_mut() {
+            for upper in b'A'..=b'Z' {
+                state[upper as usize] = state[upper.to_ascii_lowercase() as usize];
+            }
+        }
+
+        Automaton {
+            next,
+            output,
+            max_len,
+        }
+    }
+
+    // a longer token can start before a shorter one that ends first, so keep scanning until no
+    // token could start earlier than the best one found
+    fn leftmost(&self, bytes: impl Iterator<Item = u8>) -> Option<Token> {
+        let mut state = 0;
+        let mut best: Option<Token> = None;
+        for (i, b) in bytes.enumerate() {
+            if best.is_some_and(|t| i >= t.start + self.max_len) {
+                break;
+            }
+            state = self.next[state][b as usize] as usize;
+            if let Some((len, value)) = self.output[state] {
+                let start = i + 1 - len;
+                if best.is_none_or(|t| start < t.start || (start == t.start && len > t.len)) {
+                    best = Some(Token { start, len, value });
+                }
+            }
+        }
+        best
+    }
+}
+
+// Prints how every line is read with the given vocabulary: the first and last token with their
+// byte offsets and the resulting value. Lines without digits are flagged as skipped and lines
+// with a single token as such, followed by a summary. Returns the total.
+fn explain<W: Write>(filename: &str, vocabulary: &DigitVocabulary, mut out: W) -> io::Result<i32> {
+    let matcher = DigitMatcher::new(&vocabulary.tokens);
+    let mut reader = LineReader::open(filename)?;
+    let mut total = 0;
+    let mut lines = 0;
+    let mut skipped = Vec::new();
+    let mut single = 0;
+    while let Some(line) = reader.next_line()? {
+        lines += 1;
+        let tokens = matcher.first_last(line);
+        match tokens {
+            None => skipped.push(lines),
+            Some((first, last)) => {
+                total += (first.value * 10 + last.value) as i32;
+                if first == last {
+                    single += 1;
+                }
+            }
+        }
+        writeln!(out, "{}", explain_line(lines, line, tokens))?;
+    }
+    let skipped_lines = match skipped.is_empty() {
+        true => String::new(),
+        false => {
+            let numbers = skipped.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+            format!(" (lines {})", numbers.join(", "))
+        }
+    };
+    writeln!(
+        out,
+        "{} lines, total {}, {} skipped{}, {} single token",
+        lines,
+        total,
+        skipped.len(),
+        skipped_lines,
+        single
+    )?;
+    Ok(total)
+}
+
+fn explain_line(number: usize, line: &str, tokens: Option<(Token, Token)>) -> String {
+    let Some((first, last)) = tokens else {
+        return format!("{}: {:?} no digits, skipped", number, line);
+    };
+    let text = |t: Token| &line[t.start..t.start + t.len];
+    let value = first.value * 10 + last.value;
+    if first == last {
+        format!(
+            "{}: {:?} only {:?} at {} -> {} (single token)",
+            number,
+            line,
+            text(first),
+            first.start,
+            value
+        )
     } else {
-        None
+        format!(
+            "{}: {:?} first {:?} at {}, last {:?} at {} -> {}",
+            number,
+            line,
+            text(first),
+            first.start,
+            text(last),
+            last.start,
+            value
+        )
     }
 }
 
-fn match_number(mut number: String, mat: &str) -> String {
-    match mat {
-        "one" => number.push('1'),
-        "two" => number.push('2'),
-        "three" => number.push('3'),
-        "four" => number.push('4'),
-        "five" => number.push('5'),
-        "six" => number.push('6'),
-        "seven" => number.push('7'),
-        "eight" => number.push('8'),
-        "nine" => number.push('9'),
-        _ => number.push_str(mat),
+// generates `megabytes` of lines of random letters with digits and digit words mixed in
+fn generate_input(megabytes: usize) -> Vec<String> {
+    let tokens = DigitVocabulary::default().tokens;
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut lines = Vec::new();
+    let mut size = 0;
+    while size < megabytes * 1_000_000 {
+        let mut line = String::new();
+        for _ in 0..rng.gen_range(4..12) {
+            match rng.gen_range(0..8) {
+                0 => line.push_str(&tokens[rng.gen_range(0..tokens.len())].0),
+                _ => line.push(rng.gen_range(b'a'..=b'z') as char),
+            }
+        }
+        size += line.len() + 1;
+        lines.push(line);
     }
-    number
+    lines
+}
+
+fn bench(megabytes: usize) {
+    let lines = generate_input(megabytes);
+    let tokens = DigitVocabulary::default().tokens;
+    let bytes = Some(lines.iter().map(|l| l.len() + 1).sum());
+
+    bench::measure("digits only", bytes, || {
+        lines
+            .iter()
+            .map(|l| get_line_number_1(l).unwrap_or(0))
+            .sum::<i32>()
+    });
+    let matcher = DigitMatcher::new(&tokens);
+    bench::measure("automaton", bytes, || {
+        lines
+            .iter()
+            .map(|l| get_line_number_2(l, &matcher).unwrap_or(0))
+            .sum::<i32>()
+    });
+    // what part 2 used to do: run a regex per token and sort all the matches
+    let regexes = tokens
+        .iter()
+        .map(|(t, _)| Regex::new(t).unwrap())
+        .collect::<Vec<_>>();
+    bench::measure("18 regexes", bytes, || {
+        lines
+            .iter()
+            .map(|l| {
+                let mut matches = regexes
+                    .iter()
+                    .zip(&tokens)
+                    .flat_map(|(re, (_, v))| re.find_iter(l).map(move |m| (m.start(), *v)))
+                    .collect::<Vec<_>>();
+                matches.sort();
+                matches
+                    .first()
+                    .zip(matches.last())
+                    .map_or(0, |(f, l)| f.1 * 10 + l.1)
+            })
+            .sum::<u32>()
+    });
 }
 
 #[cfg(test)]
@@ -110,16 +450,79 @@ mod tests {
 
     #[test]
     fn test_get_line_number_2() {
-        let patterns = vec![
-            "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "1", "2", "3",
-            "4", "5", "6", "7", "8", "9",
-        ];
-        let regexes: Vec<Regex> = patterns.iter().map(|x| Regex::new(x).unwrap()).collect();
-        assert_eq!(get_line_number_2("yytwo1tt6three4", &regexes), Some(24));
-        assert_eq!(get_line_number_2("1twpoo43", &regexes), Some(13));
-        assert_eq!(get_line_number_2("fffsix10fghfutwo2", &regexes), Some(62));
-        assert_eq!(get_line_number_2("ddd1four", &regexes), Some(14));
-        assert_eq!(get_line_number_2("vvv", &regexes), None);
+        let matcher = DigitMatcher::new(&DigitVocabulary::default().tokens);
+        assert_eq!(get_line_number_2("yytwo1tt6three4", &matcher), Some(24));
+        assert_eq!(get_line_number_2("1twpoo43", &matcher), Some(13));
+        assert_eq!(get_line_number_2("fffsix10fghfutwo2", &matcher), Some(62));
+        assert_eq!(get_line_number_2("ddd1four", &matcher), Some(14));
+        assert_eq!(get_line_number_2("vvv", &matcher), None);
+    }
+
+    #[test]
+    fn test_overlapping_tokens() {
+        let matcher = DigitMatcher::new(&DigitVocabulary::default().tokens);
+        assert_eq!(get_line_number_2("oneight", &matcher), Some(18));
+        assert_eq!(get_line_number_2("twone", &matcher), Some(21));
+        assert_eq!(get_line_number_2("eighthree", &matcher), Some(83));
+        assert_eq!(get_line_number_2("7", &matcher), Some(77));
+        let (first, last) = matcher.first_last("xxsevenineyy").unwrap();
+        assert_eq!(
+            first,
+            Token {
+                start: 2,
+                len: 5,
+                value: 7
+            }
+        );
+        assert_eq!(
+            last,
+            Token {
+                start: 6,
+                len: 4,
+                value: 9
+            }
+        );
+    }
+
+    #[test]
+    fn test_token_inside_longer_token() {
+        // "b" is found first but "abc" starts earlier
+        let matcher = DigitMatcher::new(&[("abc", 1), ("b", 2), ("bcd", 3)]);
+        let (first, last) = matcher.first_last("xabcdx").unwrap();
+        assert_eq!(
+            first,
+            Token {
+                start: 1,
+                len: 3,
+                value: 1
+            }
+        );
+        assert_eq!(
+            last,
+            Token {
+                start: 2,
+                len: 3,
+                value: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_automaton_matches_regexes() {
+        let matcher = DigitMatcher::new(&DigitVocabulary::default().tokens);
+        for line in generate_input(1).iter().take(2000) {
+            let mut matches = DigitVocabulary::default()
+                .tokens
+                .iter()
+                .flat_map(|(t, v)| line.match_indices(t.as_str()).map(move |(i, _)| (i, *v)))
+                .collect::<Vec<_>>();
+            matches.sort();
+            let expected = matches
+                .first()
+                .zip(matches.last())
+                .map(|(f, l)| (f.1 * 10 + l.1) as i32);
+            assert_eq!(get_line_number_2(line, &matcher), expected);
+        }
     }
 
     #[test]
@@ -129,6 +532,148 @@ mod tests {
 
     #[test]
     fn test_part_2() {
-        assert_eq!(part_2("src/bin/day01/test_input_2.txt"), 281);
+        assert_eq!(
+            part_2(
+                "src/bin/day01/test_input_2.txt",
+                &DigitVocabulary::default()
+            ),
+            281
+        );
+    }
+
+    #[test]
+    fn test_vocabularies() {
+        let english = DigitVocabulary::default();
+        assert_eq!(english.tokens.len(), 18);
+        assert!(!english.tokens.contains(&("zero".to_string(), 0)));
+        let english = DigitVocabulary::new("English, zero").unwrap();
+        assert!(english.tokens.contains(&("zero".to_string(), 0)));
+        assert!(english.tokens.contains(&("0".to_string(), 0)));
+        let custom = DigitVocabulary::new("uno=1 dos=2 cero=0").unwrap();
+        assert_eq!(custom.tokens.len(), 12);
+    }
+
+    #[test]
+    fn test_invalid_vocabularies() {
+        let error = |config| DigitVocabulary::new(config).err();
+        assert_eq!(
+            error("klingon"),
+            Some("Unknown vocabulary: klingon".to_string())
+        );
+        assert_eq!(
+            error("english dix=10"),
+            Some("Invalid digit value: dix=10".to_string())
+        );
+        assert_eq!(
+            error("uno=one"),
+            Some("Invalid digit value: uno=one".to_string())
+        );
+    }
+
+    #[test]
+    fn test_line_number_in_other_languages() {
+        let line_number = |config: &str, line: &str| {
+            let matcher = DigitMatcher::new(&DigitVocabulary::new(config).unwrap().tokens);
+            get_line_number_2(line, &matcher)
+        };
+        assert_eq!(line_number("german", "fünf und zwei"), Some(52));
+        assert_eq!(line_number("german", "FÜNF"), Some(55));
+        assert_eq!(line_number("german", "nullacht"), Some(88));
+        assert_eq!(line_number("german zero", "nullacht"), Some(8));
+        assert_eq!(line_number("french zero", "ZÉRO-SEPT"), Some(7));
+        assert_eq!(line_number("english french", "one huit"), Some(18));
+        assert_eq!(line_number("roman", "xivx"), Some(44));
+        assert_eq!(line_number("roman", "Chapter VIII"), Some(88));
+        assert_eq!(line_number("uno=1 dos=2", "tres Dos uno"), Some(21));
+        assert_eq!(line_number("english", "cinq"), None);
+    }
+
+    #[test]
+    fn test_unicode_offsets() {
+        let matcher = DigitMatcher::new(&DigitVocabulary::new("german").unwrap().tokens);
+        let (first, last) = matcher.first_last("xxFÜNF3ß").unwrap();
+        assert_eq!(
+            first,
+            Token {
+                start: 2,
+                len: 5,
+                value: 5
+            }
+        );
+        assert_eq!(
+            last,
+            Token {
+                start: 7,
+                len: 1,
+                value: 3
+            }
+        );
+        // 'İ' folds to two characters, the match still covers the whole of it
+        let matcher = DigitMatcher::new(&DigitVocabulary::new("roman").unwrap().tokens);
+        let (first, last) = matcher.first_last("aİb").unwrap();
+        assert_eq!(
+            first,
+            Token {
+                start: 1,
+                len: 2,
+                value: 1
+            }
+        );
+        assert_eq!(first, last);
+    }
+
+    #[test]
+    fn test_explain() {
+        let mut output = Vec::new();
+        let total = explain(
+            "src/bin/day01/test_input_2.txt",
+            &DigitVocabulary::default(),
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(total, 281);
+        let output = String::from_utf8(output).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 8);
+        assert_eq!(
+            lines[0],
+            "1: \"two1nine\" first \"two\" at 0, last \"nine\" at 4 -> 29"
+        );
+        assert_eq!(lines[7], "7 lines, total 281, 0 skipped, 0 single token");
+    }
+
+    #[test]
+    fn test_explain_line() {
+        let matcher = DigitMatcher::new(&DigitVocabulary::default().tokens);
+        let explain = |line| explain_line(3, line, matcher.first_last(line));
+        assert_eq!(explain("abc"), "3: \"abc\" no digits, skipped");
+        assert_eq!(
+            explain("xxsevenyy"),
+            "3: \"xxsevenyy\" only \"seven\" at 2 -> 77 (single token)"
+        );
+        assert_eq!(
+            explain("7x7"),
+            "3: \"7x7\" first \"7\" at 0, last \"7\" at 2 -> 77"
+        );
+        assert_eq!(
+            explain("zweiONE"),
+            "3: \"zweiONE\" only \"ONE\" at 4 -> 11 (single token)"
+        );
+    }
+
+    #[test]
+    fn test_explain_summary() {
+        let mut output = Vec::new();
+        fuzz::with_input_file(b"abc\n7pqr\nx1y2\nnope\n", |filename| {
+            assert_eq!(
+                explain(filename, &DigitVocabulary::default(), &mut output).unwrap(),
+                77 + 12
+            );
+        });
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(
+            output.lines().last().unwrap(),
+            "4 lines, total 89, 2 skipped (lines 1, 4), 1 single token"
+        );
     }
 }
@@ -1,59 +1,93 @@
 // --- Day 2: Cube Conundrum ---
 
-use advent_of_code_2023::read_lines;
+use advent_of_code_2023::{fold_lines, fuzz, map_lines, workspace::Puzzle};
 use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead, Write};
 
+#[derive(Clone, Debug, Default, PartialEq)]
 struct CubeSet {
     cubes: HashMap<String, i32>,
 }
 
-const BLUE: &str = "blue";
-const GREEN: &str = "green";
-const RED: &str = "red";
-const COLORS: [&str; 3] = [BLUE, GREEN, RED];
-
-impl Default for CubeSet {
-    fn default() -> Self {
-        CubeSet {
-            cubes: COLORS.iter().map(|&color| (color.to_string(), 0)).collect(),
-        }
-    }
-}
-
 impl CubeSet {
     // Create a new CubeSet from a string like: 1 red, 2 green, 6 blue
     pub fn new(str_set: &str) -> CubeSet {
+        match CubeSet::parse(str_set) {
+            Some(set) => set,
+            None => panic!("Invalid cube set: {}", str_set),
+        }
+    }
+
+    // like `new`, but returns None for a malformed set, an empty string is an empty set
+    fn parse(str_set: &str) -> Option<CubeSet> {
         let mut set = CubeSet::default();
 
-        for color_and_num in str_set.split(",") {
-            let (num, color) = CubeSet::parse_color_and_num(color_and_num);
+        for color_and_num in str_set.split(",").filter(|s| !s.trim().is_empty()) {
+            let (num, color) = CubeSet::parse_color_and_num(color_and_num)?;
             set.cubes.insert(color, num);
         }
 
-        set
+        Some(set)
     }
 
-    fn parse_color_and_num(color_and_num: &str) -> (i32, String) {
+    // a set with none of each of the given colors, so they take part in the power
+    fn empty_of(colors: &CubeSet) -> CubeSet {
+        CubeSet {
+            cubes: colors
+                .cubes
+                .keys()
+                .map(|color| (color.clone(), 0))
+                .collect(),
+        }
+    }
+
+    fn parse_color_and_num(color_and_num: &str) -> Option<(i32, String)> {
         let mut split = color_and_num.split_whitespace();
-        let num = split.next().unwrap().parse::<i32>().unwrap();
-        let color = split.next().unwrap().to_string();
-        (num, color)
+        let num = split.next()?.parse::<i32>().ok()?;
+        let color = split.next()?.to_string();
+        Some((num, color))
     }
 
     fn power(&self) -> i32 {
         self.cubes.values().product()
     }
 
-    fn blue(&self) -> i32 {
-        self.cubes[BLUE]
+    // colors that are not in the set count as none
+    fn count(&self, color: &str) -> i32 {
+        self.cubes.get(color).copied().unwrap_or(0)
+    }
+
+    // true if every cube of this set is also in the bag, a color missing from the bag has none
+    fn fits_in(&self, bag: &CubeSet) -> bool {
+        self.cubes
+            .iter()
+            .all(|(color, &num)| num <= bag.count(color))
     }
 
-    fn green(&self) -> i32 {
-        self.cubes[GREEN]
+    // grow the set to hold the other one as well
+    fn extend_to(&mut self, other: &CubeSet) {
+        for (color, &num) in &other.cubes {
+            let entry = self.cubes.entry(color.clone()).or_insert(0);
+            *entry = (*entry).max(num);
+        }
     }
+}
 
-    fn red(&self) -> i32 {
-        self.cubes[RED]
+// colors in name order, colors with no cubes are left out: 6 blue, 2 green, 4 red
+impl fmt::Display for CubeSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut cubes = self
+            .cubes
+            .iter()
+            .filter(|(_, &num)| num > 0)
+            .collect::<Vec<_>>();
+        cubes.sort();
+        let cubes = cubes
+            .iter()
+            .map(|(color, num)| format!("{} {}", num, color))
+            .collect::<Vec<_>>();
+        write!(f, "{}", cubes.join(", "))
     }
 }
 
@@ -87,11 +121,12 @@ impl Game {
     /// let game = Game::new(str_input, config);
     /// ```
     pub fn new(str_input: &str, config: &str) -> Game {
+        let config = CubeSet::new(config);
         let mut game = Game {
             id: 0,
-            config: CubeSet::new(config),
+            min_set: CubeSet::empty_of(&config),
+            config,
             cube_sets: Vec::new(),
-            min_set: CubeSet::default(),
             possible: true,
         };
 
@@ -115,59 +150,423 @@ impl Game {
     }
 
     fn determine_possibility_and_minimum_set(&mut self, cubes: CubeSet) {
-        // check if the game is possible by comparing the numbers in the cube set to the config
-        if cubes.blue() > self.config.blue()
-            || cubes.green() > self.config.green()
-            || cubes.red() > self.config.red()
-        {
+        // check if the game is possible by comparing the numbers in the cube set to the config,
+        // a color the config does not know about makes the game impossible
+        if !cubes.fits_in(&self.config) {
             self.possible = false;
         }
-        // find the smallest necessary cube set for a possible game
-        if self.min_set.blue() < cubes.blue() {
-            self.min_set.cubes.insert(BLUE.to_string(), cubes.blue());
-        }
-        if self.min_set.green() < cubes.green() {
-            self.min_set.cubes.insert(GREEN.to_string(), cubes.green());
-        }
-        if self.min_set.red() < cubes.red() {
-            self.min_set.cubes.insert(RED.to_string(), cubes.red());
-        }
+        // find the smallest necessary cube set for a possible game, over every color seen
+        self.min_set.extend_to(&cubes);
         self.cube_sets.push(cubes);
     }
 }
 
 fn part_1(filename: &str, config: &str) -> i32 {
-    let lines = read_lines(filename).unwrap();
-    let mut total = 0;
-    for line in &lines {
+    fold_lines(filename, 0, |total, line| {
         let game = Game::new(line, config);
         if game.possible {
-            total += game.id;
+            total + game.id
+        } else {
+            total
         }
-    }
-    total
+    })
+    .unwrap()
 }
 
 fn part_2(filename: &str, config: &str) -> i32 {
-    let lines = read_lines(filename).unwrap();
-    let mut sum = 0;
-    for line in &lines {
+    fold_lines(filename, 0, |sum, line| {
         let game = Game::new(line, config);
-        sum += game.min_set.power();
+        sum + game.min_set.power()
+    })
+    .unwrap()
+}
+
+fn read_games(filename: &str, config: &str) -> Vec<Game> {
+    map_lines(filename, |line| Game::new(line, config)).unwrap()
+}
+
+// ids of the games whose minimum set fits in the bag
+fn possible_games(games: &[Game], bag: &CubeSet) -> Vec<i32> {
+    games
+        .iter()
+        .filter(|game| game.min_set.fits_in(bag))
+        .map(|game| game.id)
+        .collect()
+}
+
+// the smallest bag that makes all of the given games possible
+fn minimal_config(games: &[Game], ids: &[i32]) -> CubeSet {
+    let mut bag = CubeSet::default();
+    for game in games.iter().filter(|game| ids.contains(&game.id)) {
+        bag.extend_to(&game.min_set);
+    }
+    bag
+}
+
+// The bag of at most `budget` cubes that makes the most games possible, with the ids of those
+// games. The best bag is always the minimal config of the games it allows, so every color only
+// has to be tried with the counts that some game needs.
+fn best_config_for_budget(games: &[Game], budget: i32) -> (CubeSet, Vec<i32>) {
+    let mut colors = games
+        .iter()
+        .flat_map(|game| game.min_set.cubes.keys().cloned())
+        .collect::<Vec<_>>();
+    colors.sort();
+    colors.dedup();
+    let counts = colors
+        .iter()
+        .map(|color| {
+            let mut counts = games
+                .iter()
+                .map(|game| game.min_set.count(color))
+                .collect::<Vec<_>>();
+            counts.push(0);
+            counts.sort();
+            counts.dedup();
+            counts
+        })
+        .collect::<Vec<_>>();
+
+    let mut best = Vec::new();
+    let mut chosen = Vec::new();
+    search_budget(games, &colors, &counts, &mut chosen, budget, &mut best);
+    (minimal_config(games, &best), best)
+}
+
+fn search_budget(
+    games: &[Game],
+    colors: &[String],
+    counts: &[Vec<i32>],
+    chosen: &mut Vec<i32>,
+    left: i32,
+    best: &mut Vec<i32>,
+) {
+    if chosen.len() == colors.len() {
+        let bag = CubeSet {
+            cubes: colors.iter().cloned().zip(chosen.iter().copied()).collect(),
+        };
+        let ids = possible_games(games, &bag);
+        if ids.len() > best.len() {
+            *best = ids;
+        }
+        return;
+    }
+    // the counts are sorted, so once one is over the budget the rest are too
+    for &num in counts[chosen.len()].iter().take_while(|&&num| num <= left) {
+        chosen.push(num);
+        search_budget(games, colors, counts, chosen, left - num, best);
+        chosen.pop();
+    }
+}
+
+fn format_ids(ids: &[i32]) -> String {
+    ids.iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Answers one query:
+//   possible 12 red, 13 green, 14 blue   ids of the games possible with that bag
+//   minimal 1 3 4                        smallest bag for those games, all games if none are given
+//   budget 40                            most games possible with a bag of at most 40 cubes
+fn answer_query(games: &[Game], query: &str) -> String {
+    let (command, args) = query.trim().split_once(' ').unwrap_or((query.trim(), ""));
+    match command {
+        "possible" => match CubeSet::parse(args) {
+            Some(bag) => format!("games: {}", format_ids(&possible_games(games, &bag))),
+            None => format!("invalid cube set: {}", args),
+        },
+        "minimal" => {
+            let ids = args
+                .split_whitespace()
+                .map(|id| id.parse::<i32>())
+                .collect::<Result<Vec<_>, _>>();
+            match ids {
+                Ok(ids) if ids.is_empty() => {
+                    let all = games.iter().map(|game| game.id).collect::<Vec<_>>();
+                    format!("bag: {}", minimal_config(games, &all))
+                }
+                Ok(ids) => format!("bag: {}", minimal_config(games, &ids)),
+                Err(_) => format!("invalid game ids: {}", args),
+            }
+        }
+        "budget" => match args.trim().parse::<i32>() {
+            Ok(budget) => {
+                let (bag, ids) = best_config_for_budget(games, budget);
+                format!(
+                    "{} games ({}) with bag: {}",
+                    ids.len(),
+                    format_ids(&ids),
+                    bag
+                )
+            }
+            Err(_) => format!("invalid budget: {}", args),
+        },
+        _ => format!("unknown query: {}", query.trim()),
+    }
+}
+
+// answers the queries read from `input` one per line, skipping blank lines
+fn run_queries<R: BufRead, W: Write>(games: &[Game], input: R, mut output: W) -> io::Result<()> {
+    for query in input.lines() {
+        let query = query?;
+        if !query.trim().is_empty() {
+            writeln!(output, "{}", answer_query(games, &query))?;
+        }
+    }
+    Ok(())
+}
+
+struct ColorStats {
+    color: String,
+    mean: f64,
+    std_dev: f64,
+    max: i32,
+    // share of the reveals that show the color at all
+    seen: f64,
+}
+
+// distribution of the number of cubes of each color per reveal, over the reveals of all games
+fn color_stats(games: &[Game]) -> Vec<ColorStats> {
+    let reveals = games
+        .iter()
+        .flat_map(|game| &game.cube_sets)
+        .collect::<Vec<_>>();
+    let mut colors = reveals
+        .iter()
+        .flat_map(|set| set.cubes.keys().cloned())
+        .collect::<Vec<_>>();
+    colors.sort();
+    colors.dedup();
+
+    let n = reveals.len() as f64;
+    colors
+        .into_iter()
+        .map(|color| {
+            let counts = reveals
+                .iter()
+                .map(|set| set.count(&color))
+                .collect::<Vec<_>>();
+            let mean = counts.iter().sum::<i32>() as f64 / n;
+            let variance = counts
+                .iter()
+                .map(|&c| (c as f64 - mean).powi(2))
+                .sum::<f64>()
+                / n;
+            ColorStats {
+                mean,
+                std_dev: variance.sqrt(),
+                max: counts.iter().copied().max().unwrap_or(0),
+                seen: counts.iter().filter(|&&c| c > 0).count() as f64 / n,
+                color,
+            }
+        })
+        .collect()
+}
+
+fn ln_binomial(n: i32, k: i32) -> f64 {
+    (1..=k)
+        .map(|i| ((n - k + i) as f64).ln() - (i as f64).ln())
+        .sum()
+}
+
+// A reveal is modelled as a handful of cubes drawn from the bag at once (and put back after), so
+// its probability is multivariate hypergeometric. Returns -inf for a reveal the bag cannot give.
+fn log_likelihood(reveal: &CubeSet, bag: &CubeSet) -> f64 {
+    if !reveal.fits_in(bag) {
+        return f64::NEG_INFINITY;
     }
-    sum
+    let drawn = reveal.cubes.values().sum();
+    let total = bag.cubes.values().sum();
+    let ways = reveal
+        .cubes
+        .iter()
+        .map(|(color, &num)| ln_binomial(bag.count(color), num))
+        .sum::<f64>();
+    ways - ln_binomial(total, drawn)
 }
 
+fn games_log_likelihood(games: &[Game], bag: &CubeSet) -> f64 {
+    games
+        .iter()
+        .flat_map(|game| &game.cube_sets)
+        .map(|reveal| log_likelihood(reveal, bag))
+        .sum()
+}
+
+// The bag of `total` cubes under which the reveals are most likely, or None if no bag of that
+// size can give all of them. Starts from the smallest possible bag with the spare cubes given to
+// the most common colors, then moves single cubes between colors while that improves the fit.
+fn max_likelihood_bag(games: &[Game], total: i32) -> Option<CubeSet> {
+    let all = games.iter().map(|game| game.id).collect::<Vec<_>>();
+    let mut bag = minimal_config(games, &all);
+    let spare = total - bag.cubes.values().sum::<i32>();
+    if spare < 0 || bag.cubes.is_empty() {
+        return None;
+    }
+    let stats = color_stats(games);
+    let sum_of_means = stats.iter().map(|s| s.mean).sum::<f64>();
+    for s in &stats {
+        *bag.cubes.get_mut(&s.color).unwrap() += (spare as f64 * s.mean / sum_of_means) as i32;
+    }
+    // rounding down leaves a few cubes over
+    let left = total - bag.cubes.values().sum::<i32>();
+    *bag.cubes.get_mut(&stats[0].color).unwrap() += left;
+
+    let floor = minimal_config(games, &all);
+    let mut best = games_log_likelihood(games, &bag);
+    loop {
+        let mut improved = false;
+        for from in &stats {
+            for to in &stats {
+                if from.color == to.color || bag.count(&from.color) <= floor.count(&from.color) {
+                    continue;
+                }
+                let mut next = bag.clone();
+                *next.cubes.get_mut(&from.color).unwrap() -= 1;
+                *next.cubes.get_mut(&to.color).unwrap() += 1;
+                let likelihood = games_log_likelihood(games, &next);
+                if likelihood > best + 1e-9 {
+                    (bag, best, improved) = (next, likelihood, true);
+                }
+            }
+        }
+        if !improved {
+            return Some(bag);
+        }
+    }
+}
+
+// Pearson's chi-squared of a reveal against the counts the bag would give on average, unlike
+// the likelihood it does not drop just because more cubes are drawn
+fn deviation(reveal: &CubeSet, bag: &CubeSet) -> f64 {
+    if !reveal.fits_in(bag) {
+        return f64::INFINITY;
+    }
+    let drawn = reveal.cubes.values().sum::<i32>() as f64;
+    let total = bag.cubes.values().sum::<i32>() as f64;
+    bag.cubes
+        .iter()
+        .filter(|(_, &num)| num > 0)
+        .map(|(color, &num)| {
+            let expected = drawn * num as f64 / total;
+            (reveal.count(color) as f64 - expected).powi(2) / expected
+        })
+        .sum()
+}
+
+// Games whose reveals stray unusually far from the bag: the mean deviation per reveal lies more
+// than `threshold` standard deviations above that of the games overall. Returns the game ids
+// with their z-scores.
+fn outliers(games: &[Game], bag: &CubeSet, threshold: f64) -> Vec<(i32, f64)> {
+    let scores = games
+        .iter()
+        .filter(|game| !game.cube_sets.is_empty())
+        .map(|game| {
+            let total = game
+                .cube_sets
+                .iter()
+                .map(|r| deviation(r, bag))
+                .sum::<f64>();
+            (game.id, total / game.cube_sets.len() as f64)
+        })
+        .collect::<Vec<_>>();
+    let finite = scores
+        .iter()
+        .filter(|s| s.1.is_finite())
+        .collect::<Vec<_>>();
+    let n = finite.len() as f64;
+    let mean = finite.iter().map(|s| s.1).sum::<f64>() / n;
+    let std_dev = (finite.iter().map(|s| (s.1 - mean).powi(2)).sum::<f64>() / n).sqrt();
+    scores
+        .into_iter()
+        .map(|(id, score)| (id, (score - mean) / std_dev))
+        .filter(|&(_, z)| z > threshold)
+        .collect()
+}
+
+fn analyze<W: Write>(games: &[Game], candidates: &[CubeSet], mut out: W) -> io::Result<()> {
+    let reveals = games.iter().map(|game| game.cube_sets.len()).sum::<usize>();
+    writeln!(out, "{} reveals in {} games", reveals, games.len())?;
+    for s in color_stats(games) {
+        writeln!(
+            out,
+            "{}: mean {:.2}, sd {:.2}, max {}, seen in {:.0}% of reveals",
+            s.color,
+            s.mean,
+            s.std_dev,
+            s.max,
+            s.seen * 100.0
+        )?;
+    }
+
+    for candidate in candidates {
+        writeln!(
+            out,
+            "bag {}: log-likelihood {:.2}",
+            candidate,
+            games_log_likelihood(games, candidate)
+        )?;
+    }
+    let Some(total) = candidates.first().map(|c| c.cubes.values().sum()) else {
+        return Ok(());
+    };
+    let Some(bag) = max_likelihood_bag(games, total) else {
+        return writeln!(out, "no bag of {} cubes gives all reveals", total);
+    };
+    writeln!(
+        out,
+        "most likely bag of {} cubes: {} (log-likelihood {:.2})",
+        total,
+        bag,
+        games_log_likelihood(games, &bag)
+    )?;
+    for (id, z) in outliers(games, &bag, OUTLIER_THRESHOLD) {
+        writeln!(out, "outlier: game {} (z-score {:.2})", id, z)?;
+    }
+    Ok(())
+}
+
+const OUTLIER_THRESHOLD: f64 = 2.0;
+
 fn main() {
+    if fuzz::from_args("day02", fuzz_target) {
+        return;
+    }
+
+    let input = Puzzle::from_env(2023, 2).input();
+    if std::env::args().any(|arg| arg == "--query") {
+        let games = read_games(&input, "");
+        run_queries(&games, io::stdin().lock(), io::stdout()).unwrap();
+        return;
+    }
+
     let config = "12 red, 13 green, 14 blue";
-    println!("Part 1: {}", part_1("src/bin/day02/input.txt", config));
-    println!("Part 2: {}", part_2("src/bin/day02/input.txt", config));
+    if std::env::args().any(|arg| arg == "--analyze") {
+        let games = read_games(&input, "");
+        analyze(&games, &[CubeSet::new(config)], io::stdout().lock()).unwrap();
+        return;
+    }
+    println!("Part 1: {}", part_1(&input, config));
+    println!("Part 2: {}", part_2(&input, config));
+}
+
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        let config = "12 red, 13 green, 14 blue";
+        part_1(filename, config);
+        part_2(filename, config);
+    });
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const BLUE: &str = "blue";
+    const GREEN: &str = "green";
+    const RED: &str = "red";
     const CONFIG: &str = "12 red, 13 green, 14 blue";
     const CUBE_SET: &str = "  1 red, 2 green,    6 blue";
     const GAME: &str = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green";
@@ -177,32 +576,32 @@ mod tests {
     #[test]
     fn test_cube_set_new() {
         let set = CubeSet::new(CUBE_SET);
-        assert_eq!(set.blue(), 6);
-        assert_eq!(set.green(), 2);
-        assert_eq!(set.red(), 1);
+        assert_eq!(set.count(BLUE), 6);
+        assert_eq!(set.count(GREEN), 2);
+        assert_eq!(set.count(RED), 1);
     }
 
     #[test]
     fn test_game_new() {
         let game = Game::new(GAME, GAME_CONFIG);
         assert_eq!(game.id, 1);
-        assert_eq!(game.config.blue(), 1);
-        assert_eq!(game.config.green(), 1);
-        assert_eq!(game.config.red(), 1);
+        assert_eq!(game.config.count(BLUE), 1);
+        assert_eq!(game.config.count(GREEN), 1);
+        assert_eq!(game.config.count(RED), 1);
         assert_eq!(game.cube_sets.len(), 3);
-        assert_eq!(game.cube_sets[0].blue(), 3);
-        assert_eq!(game.cube_sets[0].green(), 0);
-        assert_eq!(game.cube_sets[0].red(), 4);
-        assert_eq!(game.cube_sets[1].blue(), 6);
-        assert_eq!(game.cube_sets[1].green(), 2);
-        assert_eq!(game.cube_sets[1].red(), 1);
-        assert_eq!(game.cube_sets[2].blue(), 0);
-        assert_eq!(game.cube_sets[2].green(), 2);
-        assert_eq!(game.cube_sets[2].red(), 0);
+        assert_eq!(game.cube_sets[0].count(BLUE), 3);
+        assert_eq!(game.cube_sets[0].count(GREEN), 0);
+        assert_eq!(game.cube_sets[0].count(RED), 4);
+        assert_eq!(game.cube_sets[1].count(BLUE), 6);
+        assert_eq!(game.cube_sets[1].count(GREEN), 2);
+        assert_eq!(game.cube_sets[1].count(RED), 1);
+        assert_eq!(game.cube_sets[2].count(BLUE), 0);
+        assert_eq!(game.cube_sets[2].count(GREEN), 2);
+        assert_eq!(game.cube_sets[2].count(RED), 0);
         assert_eq!(game.possible, false);
-        assert_eq!(game.min_set.blue(), 6);
-        assert_eq!(game.min_set.green(), 2);
-        assert_eq!(game.min_set.red(), 4);
+        assert_eq!(game.min_set.count(BLUE), 6);
+        assert_eq!(game.min_set.count(GREEN), 2);
+        assert_eq!(game.min_set.count(RED), 4);
     }
 
     #[test]
@@ -214,4 +613,137 @@ mod tests {
     fn test_part_2() {
         assert_eq!(part_2(TEST_FILENAME, CONFIG), 2286);
     }
+
+    #[test]
+    fn test_any_colors() {
+        let config = "2 cyan, 3 magenta, 1 yellow";
+        let game = Game::new("Game 7: 2 cyan, 1 yellow; 3 magenta", config);
+        assert!(game.possible);
+        assert_eq!(game.min_set.power(), 6);
+
+        // an unknown color makes the game impossible but still counts towards the minimum set
+        let game = Game::new("Game 8: 2 cyan, 1 yellow; 3 magenta, 1 black", config);
+        assert!(!game.possible);
+        assert_eq!(game.min_set.count("black"), 1);
+        assert_eq!(game.min_set.power(), 6);
+
+        // a color of the config that is never revealed is needed zero times
+        let game = Game::new("Game 9: 2 cyan; 3 magenta", config);
+        assert!(game.possible);
+        assert_eq!(game.min_set.count("yellow"), 0);
+        assert_eq!(game.min_set.power(), 0);
+    }
+
+    #[test]
+    fn test_queries() {
+        let games = read_games(TEST_FILENAME, "");
+        assert_eq!(possible_games(&games, &CubeSet::new(CONFIG)), vec![1, 2, 5]);
+        assert_eq!(
+            minimal_config(&games, &[1]).to_string(),
+            "6 blue, 2 green, 4 red"
+        );
+        assert_eq!(
+            minimal_config(&games, &[1, 3]).to_string(),
+            "6 blue, 13 green, 20 red"
+        );
+        // games 1, 2 and 5 need 6 blue, 3 green and 6 red between them
+        let (bag, ids) = best_config_for_budget(&games, 15);
+        assert_eq!(ids, vec![1, 2, 5]);
+        assert_eq!(bag.to_string(), "6 blue, 3 green, 6 red");
+        let (_, ids) = best_config_for_budget(&games, 14);
+        assert_eq!(ids.len(), 2);
+        let (bag, ids) = best_config_for_budget(&games, 0);
+        assert!(ids.is_empty());
+        assert_eq!(bag.to_string(), "");
+    }
+
+    #[test]
+    fn test_run_queries() {
+        let games = read_games(TEST_FILENAME, "");
+        let queries = "possible 12 red, 13 green, 14 blue\nminimal 2 5\n\nbudget 15\n\
+                       possible 12 red, lots\nminimal x\nfly\n";
+        let mut output = Vec::new();
+        run_queries(&games, queries.as_bytes(), &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "games: 1, 2, 5\n\
+             bag: 4 blue, 3 green, 6 red\n\
+             3 games (1, 2, 5) with bag: 6 blue, 3 green, 6 red\n\
+             invalid cube set: 12 red, lots\n\
+             invalid game ids: x\n\
+             unknown query: fly\n"
+        );
+    }
+
+    #[test]
+    fn test_color_stats() {
+        let games = read_games(TEST_FILENAME, "");
+        let stats = color_stats(&games);
+        assert_eq!(stats.len(), 3);
+        assert_eq!(stats[0].color, BLUE);
+        assert_eq!(stats[0].max, 15);
+        // blue shows in 11 of the 14 reveals, 50 cubes in all
+        assert!((stats[0].seen - 11.0 / 14.0).abs() < 1e-9);
+        assert!((stats[0].mean - 50.0 / 14.0).abs() < 1e-9);
+        assert_eq!(stats[2].color, RED);
+        assert_eq!(stats[2].max, 20);
+    }
+
+    #[test]
+    fn test_log_likelihood() {
+        let bag = CubeSet::new("2 red, 1 blue");
+        // two of the three ways to draw two cubes give a red and the blue
+        let p = log_likelihood(&CubeSet::new("1 red, 1 blue"), &bag).exp();
+        assert!((p - 2.0 / 3.0).abs() < 1e-9);
+        let p = log_likelihood(&CubeSet::new("2 red"), &bag).exp();
+        assert!((p - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(
+            log_likelihood(&CubeSet::new("2 blue"), &bag),
+            f64::NEG_INFINITY
+        );
+    }
+
+    #[test]
+    fn test_max_likelihood_bag() {
+        // nine single red cubes and three single blue ones are most likely from a bag of 3 to 1
+        let games = (1..=12)
+            .map(|id| {
+                let color = if id % 4 == 0 { BLUE } else { RED };
+                Game::new(&format!("Game {}: 1 {}", id, color), "")
+            })
+            .collect::<Vec<_>>();
+        let bag = max_likelihood_bag(&games, 8).unwrap();
+        assert_eq!(bag.to_string(), "2 blue, 6 red");
+        assert!(max_likelihood_bag(&games, 1).is_none());
+    }
+
+    #[test]
+    fn test_outliers() {
+        let mut games = (1..=10)
+            .map(|id| Game::new(&format!("Game {}: 1 red, 1 blue; 2 red", id), ""))
+            .collect::<Vec<_>>();
+        games.push(Game::new("Game 11: 2 blue; 2 blue", ""));
+        let bag = max_likelihood_bag(&games, 8).unwrap();
+        let outliers = outliers(&games, &bag, OUTLIER_THRESHOLD);
+        assert_eq!(outliers.len(), 1);
+        assert_eq!(outliers[0].0, 11);
+    }
+
+    #[test]
+    fn test_analyze() {
+        let games = read_games(TEST_FILENAME, "");
+        let mut output = Vec::new();
+        analyze(&games, &[CubeSet::new(CONFIG)], &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "14 reveals in 5 games");
+        assert!(lines[1].starts_with("blue: mean 3.57"));
+        // the puzzle bag cannot give the 20 red of game 3
+        assert_eq!(
+            lines[4],
+            "bag 14 blue, 13 green, 12 red: log-likelihood -inf"
+        );
+        // and no bag of that size gives all reveals, they need 48 cubes
+        assert_eq!(lines[5], "no bag of 39 cubes gives all reveals");
+    }
 }
@@ -1,6 +1,15 @@
 // --- Day 3: Gear Ratios ---
+use advent_of_code_2023::{bench, fuzz, workspace::Puzzle};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fs;
+use std::io::{self, BufRead};
+
 struct Number {
     value: i32,
+    // row and first column of the number
+    position: (usize, usize),
+    len: usize,
     adjacent: Vec<(usize, usize)>,
 }
 
@@ -8,19 +17,24 @@ impl Number {
     fn from_string(s: &str) -> Number {
         Number {
             value: s.parse::<i32>().unwrap(),
+            position: (0, 0),
+            len: s.len(),
             adjacent: vec![],
         }
     }
 }
 
+// a symbol with the numbers around it, a gear is a `*` with exactly two of them
 struct Gear<'a> {
+    symbol: char,
     position: (usize, usize),
     parts: Vec<&'a Number>,
 }
 
 impl<'a> Gear<'a> {
-    fn new(position: (usize, usize)) -> Gear<'a> {
+    fn new(symbol: char, position: (usize, usize)) -> Gear<'a> {
         Gear {
+            symbol,
             position,
             parts: vec![],
         }
@@ -35,116 +49,502 @@ impl<'a> Gear<'a> {
     }
 }
 
-fn main() {
-    println!("Part 1: {}", part_1("src/bin/day03/input.txt"));
-    println!("Part 2: {}", part_2("src/bin/day03/input.txt"));
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Highlight {
+    Plain,
+    Part,
+    NotPart,
+    Gear,
+    Symbol,
 }
 
-fn part_1(filename: &str) -> i32 {
-    let mut sum = 0;
-    let lines = advent_of_code_2023::read_lines(filename).unwrap();
-    let mut numbers = Vec::new();
-    let mut symbols: Vec<(usize, usize)> = Vec::new();
-    for (y, line) in lines.iter().enumerate() {
-        let mut chars = line.chars().collect::<Vec<_>>();
-        chars.push(' ');
-        let mut i = 0;
-        while i < chars.len() - 1 {
-            if chars[i].is_digit(10) {
-                i = process_number(&chars, i, y, line, &lines, &mut numbers);
-            } else if chars[i] != '.' {
-                symbols.push((y, i));
-            }
-            i += 1;
+impl Highlight {
+    fn ansi(&self) -> &'static str {
+        match self {
+            Highlight::Plain => "\x1b[0m",
+            Highlight::Part => "\x1b[32m",
+            Highlight::NotPart => "\x1b[31m",
+            Highlight::Gear => "\x1b[1;33m",
+            Highlight::Symbol => "\x1b[36m",
         }
     }
-    for num in &numbers {
-        add_if_part(num, &symbols, &mut sum);
+
+    fn class(&self) -> &'static str {
+        match self {
+            Highlight::Plain => "",
+            Highlight::Part => "part",
+            Highlight::NotPart => "not-part",
+            Highlight::Gear => "gear",
+            Highlight::Symbol => "symbol",
+        }
     }
-    sum
 }
 
-fn part_2(filename: &str) -> i32 {
-    let lines = advent_of_code_2023::read_lines(filename).unwrap();
-    let mut numbers = Vec::new();
-    let mut gear: Vec<Gear> = Vec::new();
-    for (y, line) in lines.iter().enumerate() {
-        let mut chars = line.chars().collect::<Vec<_>>();
-        chars.push(' ');
-        let mut i = 0;
-        while i < chars.len() - 1 {
-            if chars[i].is_digit(10) {
-                i = process_number(&chars, i, y, line, &lines, &mut numbers);
-            } else if chars[i] == '*' {
-                gear.push(Gear::new((y, i)));
+const HTML_STYLE: &str = "pre { background: #111; color: #888; }\n\
+    .part { color: #4c4; }\n\
+    .not-part { color: #e44; }\n\
+    .gear { color: #ec3; font-weight: bold; }\n\
+    .symbol { color: #4cc; }";
+
+const NO_NUMBER: u32 = u32::MAX;
+
+// The schematic with a grid index over it: every cell knows the id of the number covering it and
+// the symbol on it, so looking up the neighbours of a number or a symbol is constant time.
+struct Schematic {
+    width: usize,
+    height: usize,
+    numbers: Vec<Number>,
+    symbols: Vec<(usize, usize)>,
+    number_at: Vec<u32>,
+    symbol_at: Vec<Option<char>>,
+}
+
+impl Schematic {
+    fn new(lines: &[String]) -> Schematic {
+        let height = lines.len();
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        let mut schematic = Schematic {
+            width,
+            height,
+            numbers: Vec::new(),
+            symbols: Vec::new(),
+            number_at: vec![NO_NUMBER; width * height],
+            symbol_at: vec![None; width * height],
+        };
+        for (y, line) in lines.iter().enumerate() {
+            let chars = line.chars().collect::<Vec<_>>();
+            let mut x = 0;
+            while x < chars.len() {
+                if chars[x].is_ascii_digit() {
+                    let start = x;
+                    while x < chars.len() && chars[x].is_ascii_digit() {
+                        x += 1;
+                    }
+                    let digits: String = chars[start..x].iter().collect();
+                    schematic.add_number(&digits, (y, start));
+                    continue;
+                }
+                if chars[x] != '.' {
+                    schematic.symbol_at[y * width + x] = Some(chars[x]);
+                    schematic.symbols.push((y, x));
+                }
+                x += 1;
             }
-            i += 1;
-        }
-    }
-
-    find_sum_of_gear_ratios(&numbers, &mut gear)
-}
-
-fn process_number(
-    chars: &[char],
-    i: usize,
-    y: usize,
-    line: &str,
-    lines: &[String],
-    numbers: &mut Vec<Number>,
-) -> usize {
-    let start = i;
-    let mut i = start;
-    while chars[i].is_digit(10) {
-        i += 1;
-    }
-    let end = i - 1;
-    let digits: String = chars[start..=end].iter().collect();
-    let mut number = Number::from_string(&digits);
-    let x1 = if start > 0 {
-        number.adjacent.push((y, start - 1));
-        start - 1
-    } else {
-        0
-    };
-    let x2 = if end < line.len() - 1 {
-        number.adjacent.push((y, end + 1));
-        end + 1
-    } else {
-        line.len() - 1
-    };
-    for i in x1..=x2 {
-        if y > 0 {
-            number.adjacent.push((y - 1, i));
         }
-        if y < lines.len() - 1 {
-            number.adjacent.push((y + 1, i));
+        schematic
+    }
+
+    fn add_number(&mut self, digits: &str, position: (usize, usize)) {
+        let (y, start) = position;
+        let id = self.numbers.len() as u32;
+        let mut number = Number::from_string(digits);
+        number.position = position;
+        let end = start + number.len - 1;
+        for x in start..=end {
+            self.number_at[y * self.width + x] = id;
+        }
+        let x1 = start.saturating_sub(1);
+        let x2 = (end + 1).min(self.width - 1);
+        if start > 0 {
+            number.adjacent.push((y, start - 1));
+        }
+        if end + 1 < self.width {
+            number.adjacent.push((y, end + 1));
+        }
+        for x in x1..=x2 {
+            if y > 0 {
+                number.adjacent.push((y - 1, x));
+            }
+            if y + 1 < self.height {
+                number.adjacent.push((y + 1, x));
+            }
+        }
+        self.numbers.push(number);
+    }
+
+    fn symbol(&self, (y, x): (usize, usize)) -> Option<char> {
+        self.symbol_at[y * self.width + x]
+    }
+
+    fn number(&self, (y, x): (usize, usize)) -> Option<&Number> {
+        match self.number_at[y * self.width + x] {
+            NO_NUMBER => None,
+            id => Some(&self.numbers[id as usize]),
+        }
+    }
+
+    // a part number has a symbol in one of the cells around it
+    fn is_part(&self, number: &Number) -> bool {
+        number
+            .adjacent
+            .iter()
+            .any(|&pos| self.symbol(pos).is_some())
+    }
+
+    fn part_numbers(&self) -> impl Iterator<Item = &Number> {
+        self.numbers.iter().filter(|n| self.is_part(n))
+    }
+
+    // the distinct numbers in the eight cells around a position, in reading order
+    fn numbers_around(&self, (y, x): (usize, usize)) -> Vec<&Number> {
+        let mut found: Vec<&Number> = Vec::new();
+        for ny in y.saturating_sub(1)..=(y + 1).min(self.height - 1) {
+            for nx in x.saturating_sub(1)..=(x + 1).min(self.width - 1) {
+                if let Some(number) = self.number((ny, nx)) {
+                    if !found.iter().any(|&f| std::ptr::eq(f, number)) {
+                        found.push(number);
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    fn gears(&self) -> Vec<Gear<'_>> {
+        self.symbols_with_parts(Some('*'))
+    }
+
+    // every symbol, or every symbol of the given character, with the numbers around it
+    fn symbols_with_parts(&self, symbol: Option<char>) -> Vec<Gear<'_>> {
+        self.symbols
+            .iter()
+            .filter_map(|&pos| {
+                let c = self.symbol(pos)?;
+                if symbol.is_some_and(|s| s != c) {
+                    return None;
+                }
+                let mut gear = Gear::new(c, pos);
+                gear.parts = self.numbers_around(gear.position);
+                Some(gear)
+            })
+            .collect()
+    }
+
+    // numbers with no symbol around them
+    fn lonely_numbers(&self) -> impl Iterator<Item = &Number> {
+        self.numbers.iter().filter(|n| !self.is_part(n))
+    }
+
+    fn query(&self, query: &Query) -> Vec<Gear<'_>> {
+        let mut found = self.symbols_with_parts(query.symbol);
+        found.retain(|g| query.arity.matches(g.parts.len()));
+        found
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Arity {
+    Any,
+    Exactly(usize),
+    AtLeast(usize),
+}
+
+impl Arity {
+    fn matches(&self, n: usize) -> bool {
+        match self {
+            Arity::Any => true,
+            Arity::Exactly(k) => n == *k,
+            Arity::AtLeast(k) => n >= *k,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Aggregate {
+    List,
+    Sum,
+    Product,
+    Max,
+}
+
+impl Aggregate {
+    fn apply(&self, parts: &[&Number]) -> i64 {
+        let values = parts.iter().map(|n| n.value as i64);
+        match self {
+            Aggregate::List | Aggregate::Sum => values.sum(),
+            Aggregate::Product => values.product(),
+            Aggregate::Max => values.max().unwrap_or(0),
+        }
+    }
+}
+
+// Which symbols to look at and what to do with the numbers around them, parsed from a query like
+// `* =2 product`: a symbol character (or `any`), optionally the number of parts as `=k` or `>=k`,
+// and `list`, `sum`, `product` or `max`. The aggregate is taken per symbol and summed over them,
+// so `* =2 product` is the sum of the gear ratios.
+#[derive(Debug, PartialEq)]
+struct Query {
+    symbol: Option<char>,
+    arity: Arity,
+    aggregate: Aggregate,
+}
+
+impl Query {
+    fn new(query: &str) -> Result<Query, String> {
+        let mut words = query.split_whitespace();
+        let symbol = match words.next() {
+            Some("any") => None,
+            Some(s) if s.chars().count() == 1 => s.chars().next(),
+            Some(s) => return Err(format!("invalid symbol: {}", s)),
+            None => return Err("empty query".to_string()),
+        };
+        let mut query = Query {
+            symbol,
+            arity: Arity::Any,
+            aggregate: Aggregate::List,
+        };
+        for word in words {
+            let count = |k: &str| k.parse().map_err(|_| format!("invalid count: {}", word));
+            if let Some(k) = word.strip_prefix(">=") {
+                query.arity = Arity::AtLeast(count(k)?);
+            } else if let Some(k) = word.strip_prefix('=') {
+                query.arity = Arity::Exactly(count(k)?);
+            } else {
+                query.aggregate = match word {
+                    "list" => Aggregate::List,
+                    "sum" => Aggregate::Sum,
+                    "product" => Aggregate::Product,
+                    "max" => Aggregate::Max,
+                    _ => return Err(format!("unknown aggregate: {}", word)),
+                };
+            }
         }
+        Ok(query)
     }
-    numbers.push(number);
-    end
 }
 
-fn add_if_part(num: &Number, symbols: &[(usize, usize)], sum: &mut i32) {
-    for pos in &num.adjacent {
-        if symbols.contains(pos) {
-            *sum += num.value;
-            break;
+impl Schematic {
+    // what every cell is shown as: numbers by whether they are parts, `*` with exactly two numbers
+    // around it as gears
+    fn highlights(&self) -> Vec<Highlight> {
+        let mut highlights = vec![Highlight::Plain; self.width * self.height];
+        for number in &self.numbers {
+            let (y, x) = number.position;
+            let highlight = match self.is_part(number) {
+                true => Highlight::Part,
+                false => Highlight::NotPart,
+            };
+            highlights[y * self.width + x..y * self.width + x + number.len].fill(highlight);
         }
+        for gear in self.symbols_with_parts(None) {
+            let (y, x) = gear.position;
+            highlights[y * self.width + x] = match gear.symbol == '*' && gear.parts.len() == 2 {
+                true => Highlight::Gear,
+                false => Highlight::Symbol,
+            };
+        }
+        highlights
+    }
+
+    // the lines of the schematic split into runs of the same highlight
+    fn runs<'a>(&self, lines: &'a [String]) -> Vec<Vec<(Highlight, &'a str)>> {
+        let highlights = self.highlights();
+        lines
+            .iter()
+            .enumerate()
+            .map(|(y, line)| {
+                let mut runs: Vec<(Highlight, &str)> = Vec::new();
+                let mut start = 0;
+                for (x, (i, _)) in line.char_indices().enumerate().skip(1) {
+                    if highlights[y * self.width + x] != highlights[y * self.width + x - 1] {
+                        runs.push((highlights[y * self.width + x - 1], &line[start..i]));
+                        start = i;
+                    }
+                }
+                if start < line.len() {
+                    let last = line[..start].chars().count();
+                    runs.push((highlights[y * self.width + last], &line[start..]));
+                }
+                runs
+            })
+            .collect()
+    }
+
+    fn render_ansi(&self, lines: &[String]) -> String {
+        let mut out = String::new();
+        for runs in self.runs(lines) {
+            for (highlight, text) in runs {
+                out.push_str(highlight.ansi());
+                out.push_str(text);
+            }
+            out.push_str(Highlight::Plain.ansi());
+            out.push('\n');
+        }
+        out
+    }
+
+    fn render_html(&self, lines: &[String]) -> String {
+        let mut out = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<style>\n{}\n</style>\n</head>\n<body>\n<pre>\n",
+            HTML_STYLE
+        );
+        for runs in self.runs(lines) {
+            for (highlight, text) in runs {
+                let text = text
+                    .replace('&', "&amp;")
+                    .replace('<', "&lt;")
+                    .replace('>', "&gt;");
+                match highlight {
+                    Highlight::Plain => out.push_str(&text),
+                    _ => out.push_str(&format!(
+                        "<span class=\"{}\">{}</span>",
+                        highlight.class(),
+                        text
+                    )),
+                }
+            }
+            out.push('\n');
+        }
+        out.push_str("</pre>\n</body>\n</html>\n");
+        out
+    }
+}
+
+// Answers one query, `lonely` lists the numbers with no symbol around them
+fn answer_query(schematic: &Schematic, query: &str) -> String {
+    if query.trim() == "lonely" {
+        let values = schematic
+            .lonely_numbers()
+            .map(|n| n.value.to_string())
+            .collect::<Vec<_>>();
+        return format!("lonely: {}", values.join(", "));
+    }
+    let query = match Query::new(query) {
+        Ok(query) => query,
+        Err(e) => return e,
+    };
+    let found = schematic.query(&query);
+    if query.aggregate != Aggregate::List {
+        let total = found
+            .iter()
+            .map(|g| query.aggregate.apply(&g.parts))
+            .sum::<i64>();
+        return format!("total: {}", total);
     }
+    found
+        .iter()
+        .map(|g| {
+            let values = g
+                .parts
+                .iter()
+                .map(|n| n.value.to_string())
+                .collect::<Vec<_>>();
+            format!(
+                "{} at {},{}: {}",
+                g.symbol,
+                g.position.0,
+                g.position.1,
+                values.join(", ")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
-fn find_sum_of_gear_ratios<'a>(numbers: &'a [Number], gear: &'a mut [Gear<'a>]) -> i32 {
-    let mut sum = 0;
-    for g in gear.iter_mut() {
-        for num in numbers {
-            if num.adjacent.contains(&g.position) {
-                g.parts.push(num);
+fn main() {
+    if fuzz::from_args("day03", fuzz_target) {
+        return;
+    }
+    if let Some(size) = bench::from_args(2000) {
+        bench(size);
+        return;
+    }
+
+    let input = Puzzle::from_env(2023, 3).input();
+    if std::env::args().any(|arg| arg == "--query") {
+        let lines = advent_of_code_2023::read_lines(&input).unwrap();
+        let schematic = Schematic::new(&lines);
+        for query in io::stdin().lock().lines() {
+            println!("{}", answer_query(&schematic, &query.unwrap()));
+        }
+        return;
+    }
+    let args = std::env::args().collect::<Vec<_>>();
+    if let Some(idx) = args.iter().position(|arg| arg == "--render") {
+        let lines = advent_of_code_2023::read_lines(&input).unwrap();
+        let schematic = Schematic::new(&lines);
+        match args.get(idx + 1) {
+            Some(file) if file.ends_with(".html") => {
+                fs::write(file, schematic.render_html(&lines)).unwrap()
             }
+            _ => print!("{}", schematic.render_ansi(&lines)),
         }
-        sum += g.ratio();
+        return;
     }
-    sum
+    println!("Part 1: {}", part_1(&input));
+    println!("Part 2: {}", part_2(&input));
+}
+
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        part_1(filename);
+        part_2(filename);
+    });
+}
+
+fn part_1(filename: &str) -> i32 {
+    let lines = advent_of_code_2023::read_lines(filename).unwrap();
+    let schematic = Schematic::new(&lines);
+    schematic.part_numbers().map(|n| n.value).sum()
+}
+
+fn part_2(filename: &str) -> i32 {
+    let lines = advent_of_code_2023::read_lines(filename).unwrap();
+    let schematic = Schematic::new(&lines);
+    schematic.gears().iter().map(Gear::ratio).sum()
+}
+
+// a `size` by `size` schematic with numbers of up to three digits and a few symbols, gears mostly
+fn generate_schematic(size: usize) -> Vec<String> {
+    let mut rng = StdRng::seed_from_u64(3);
+    (0..size)
+        .map(|_| {
+            let mut line = String::with_capacity(size);
+            while line.len() < size {
+                match rng.gen_range(0..20) {
+                    0..=2 => {
+                        let digits = rng.gen_range(1..=3).min(size - line.len());
+                        for _ in 0..digits {
+                            line.push(rng.gen_range(b'1'..=b'9') as char);
+                        }
+                    }
+                    3 => line.push('*'),
+                    4 => line.push(['#', '$', '+', '/', '@'][rng.gen_range(0..5)]),
+                    _ => line.push('.'),
+                }
+                // keep numbers apart so they do not run into each other
+                if line.len() < size {
+                    line.push('.');
+                }
+            }
+            line
+        })
+        .collect()
+}
+
+fn bench(size: usize) {
+    let lines = generate_schematic(size);
+    let bytes = Some(size * (size + 1));
+    bench::measure("index", bytes, || Schematic::new(&lines));
+    let schematic = Schematic::new(&lines);
+    println!(
+        "{} numbers, {} symbols",
+        schematic.numbers.len(),
+        schematic.symbols.len()
+    );
+    bench::measure("part numbers", bytes, || {
+        schematic
+            .part_numbers()
+            .map(|n| n.value as i64)
+            .sum::<i64>()
+    });
+    bench::measure("gear ratios", bytes, || {
+        schematic
+            .gears()
+            .iter()
+            .map(|g| g.ratio() as i64)
+            .sum::<i64>()
+    });
 }
 
 #[cfg(test)]
@@ -160,4 +560,136 @@ mod tests {
     fn test_part_2() {
         assert_eq!(part_2("src/bin/day03/test_input.txt"), 467835);
     }
+
+    fn schematic(input: &str) -> Schematic {
+        let lines = input.lines().map(String::from).collect::<Vec<_>>();
+        Schematic::new(&lines)
+    }
+
+    #[test]
+    fn test_schematic_index() {
+        let lines = advent_of_code_2023::read_lines("src/bin/day03/test_input.txt").unwrap();
+        let schematic = Schematic::new(&lines);
+        assert_eq!(schematic.numbers.len(), 10);
+        assert_eq!(schematic.symbols.len(), 6);
+        assert_eq!(schematic.number((0, 2)).unwrap().value, 467);
+        assert_eq!(schematic.number((0, 3)).map(|n| n.value), None);
+        assert_eq!(schematic.symbol((1, 3)), Some('*'));
+        let around = schematic.numbers_around((1, 3));
+        assert_eq!(
+            around.iter().map(|n| n.value).collect::<Vec<_>>(),
+            [467, 35]
+        );
+        assert!(!schematic.is_part(schematic.number((0, 5)).unwrap()));
+        assert!(schematic.is_part(schematic.number((2, 2)).unwrap()));
+    }
+
+    #[test]
+    fn test_schematic_edges() {
+        // numbers touching the borders, on ragged lines and counted once per gear
+        let schematic = schematic("12*\n..34\n5\n*.");
+        let values = schematic
+            .part_numbers()
+            .map(|n| n.value)
+            .collect::<Vec<_>>();
+        assert_eq!(values, [12, 34, 5]);
+        let gears = schematic.gears();
+        assert_eq!(gears.len(), 2);
+        assert_eq!(gears[0].ratio(), 12 * 34);
+        assert_eq!(gears[1].parts.len(), 1);
+        assert_eq!(gears[1].ratio(), 0);
+    }
+
+    #[test]
+    fn test_generated_schematic() {
+        let lines = generate_schematic(200);
+        assert_eq!(lines.len(), 200);
+        assert!(lines.iter().all(|l| l.len() == 200));
+        let schematic = Schematic::new(&lines);
+        // every gear part also counts as a part number
+        let parts = schematic.part_numbers().count();
+        assert!(schematic.gears().iter().all(|g| g.parts.len() <= parts));
+        assert!(parts > 0 && parts < schematic.numbers.len());
+    }
+
+    #[test]
+    fn test_parse_query() {
+        assert_eq!(
+            Query::new("* =2 product"),
+            Ok(Query {
+                symbol: Some('*'),
+                arity: Arity::Exactly(2),
+                aggregate: Aggregate::Product,
+            })
+        );
+        assert_eq!(
+            Query::new("any >=1"),
+            Ok(Query {
+                symbol: None,
+                arity: Arity::AtLeast(1),
+                aggregate: Aggregate::List,
+            })
+        );
+        assert_eq!(Query::new("** sum"), Err("invalid symbol: **".to_string()));
+        assert_eq!(Query::new("# =x"), Err("invalid count: =x".to_string()));
+        assert_eq!(
+            Query::new("# avg"),
+            Err("unknown aggregate: avg".to_string())
+        );
+    }
+
+    #[test]
+    fn test_answer_query() {
+        let lines = advent_of_code_2023::read_lines("src/bin/day03/test_input.txt").unwrap();
+        let schematic = Schematic::new(&lines);
+        assert_eq!(answer_query(&schematic, "* =2 product"), "total: 467835");
+        assert_eq!(answer_query(&schematic, "* =1"), "* at 4,3: 617");
+        assert_eq!(answer_query(&schematic, "any >=2 sum"), "total: 1855");
+        assert_eq!(answer_query(&schematic, "any max"), "total: 3728");
+        assert_eq!(answer_query(&schematic, "$"), "$ at 8,3: 664");
+        assert_eq!(answer_query(&schematic, "%"), "");
+        assert_eq!(answer_query(&schematic, "lonely"), "lonely: 114, 58");
+    }
+
+    #[test]
+    fn test_highlights() {
+        let lines = advent_of_code_2023::read_lines("src/bin/day03/test_input.txt").unwrap();
+        let schematic = Schematic::new(&lines);
+        let highlights = schematic.highlights();
+        let at = |y: usize, x: usize| highlights[y * schematic.width + x];
+        assert_eq!(at(0, 0), Highlight::Part);
+        assert_eq!(at(0, 5), Highlight::NotPart);
+        assert_eq!(at(0, 3), Highlight::Plain);
+        assert_eq!(at(1, 3), Highlight::Gear);
+        // a `*` next to a single number is not a gear
+        assert_eq!(at(4, 3), Highlight::Symbol);
+        assert_eq!(at(3, 6), Highlight::Symbol);
+    }
+
+    #[test]
+    fn test_render() {
+        let lines = vec!["467..114".to_string(), "...*....".to_string()];
+        let schematic = Schematic::new(&lines);
+        assert_eq!(
+            schematic.render_ansi(&lines),
+            "\x1b[32m467\x1b[0m..\x1b[31m114\x1b[0m\n\
+             \x1b[0m...\x1b[36m*\x1b[0m....\x1b[0m\n"
+        );
+        let html = schematic.render_html(&lines);
+        assert!(html.contains(
+            "<span class=\"part\">467</span>..<span class=\"not-part\">114</span>\n\
+             ...<span class=\"symbol\">*</span>....\n"
+        ));
+    }
+
+    #[test]
+    fn test_render_ragged_unicode() {
+        let lines = vec!["é1&".to_string(), "<".to_string()];
+        let schematic = Schematic::new(&lines);
+        let html = schematic.render_html(&lines);
+        assert!(html.contains(
+            "<span class=\"symbol\">é</span><span class=\"part\">1</span>\
+             <span class=\"symbol\">&amp;</span>\n<span class=\"symbol\">&lt;</span>\n"
+        ));
+    }
 }
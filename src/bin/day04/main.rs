@@ -1,6 +1,8 @@
 // --- Day 4: Scratchcards ---
 
-use std::collections::HashMap;
+use advent_of_code_2023::{fold_lines, fuzz, map_lines, workspace::Puzzle};
+use std::collections::HashSet;
+use std::fs;
 
 #[derive(Default, Debug)]
 struct Scratchcard {
@@ -31,48 +33,273 @@ impl Scratchcard {
     }
 
     fn calculate_points(&mut self) {
-        self.points = self
-            .winning
+        self.points = self.matching().len() as u32
+    }
+
+    // the winning numbers that we have, in the order they are listed
+    fn matching(&self) -> Vec<u32> {
+        let numbers = self.numbers.iter().collect::<HashSet<_>>();
+        self.winning
             .iter()
-            .filter(|x| self.numbers.contains(x))
-            .count() as u32
+            .filter(|x| numbers.contains(x))
+            .copied()
+            .collect()
+    }
+
+    // the first match is worth one point, every other doubles it, more than 64 matches don't fit
+    fn score(&self) -> Option<u64> {
+        match self.points {
+            0 => Some(0),
+            points => 1u64.checked_shl(points - 1),
+        }
+    }
+
+    fn checked_score(&self) -> Result<u64, String> {
+        self.score()
+            .ok_or_else(|| format!("Too many points: card {}", self.id))
     }
 }
 
-fn main() {
-    println!("Part 1: {}", part_1("src/bin/day04/input.txt"));
-    println!("Part 2: {}", part_2("src/bin/day04/input.txt"));
-}
-
-fn part_1(filename: &str) -> i32 {
-    let lines = advent_of_code_2023::read_lines(filename).unwrap();
-    let mut total = 0;
-    for line in lines {
-        let card = Scratchcard::new(&line);
-        total += if card.points > 0 {
-            2_i32.pow(card.points - 1)
-        } else {
-            0
-        };
+// How cards win copies of later cards: a card with n matches wins `multiplier` copies of each of
+// the n cards starting `ahead` cards after it. With `cap` the copies of cards past the end of the
+// deck are dropped, otherwise they are counted even though they win nothing themselves.
+#[derive(Debug, PartialEq)]
+struct CopyRules {
+    ahead: usize,
+    multiplier: u64,
+    cap: bool,
+}
+
+impl Default for CopyRules {
+    fn default() -> Self {
+        CopyRules {
+            ahead: 1,
+            multiplier: 1,
+            cap: true,
+        }
     }
-    total
 }
 
-fn part_2(filename: &str) -> u32 {
-    let mut cards: HashMap<u32, u32> = HashMap::new();
-    let lines = advent_of_code_2023::read_lines(filename).unwrap();
-    for line in lines {
-        let card = Scratchcard::new(&line);
-        *cards.entry(card.id).or_insert(0) += 1;
-        if card.points > 0 {
-            for _i in 0..cards[&card.id] {
-                for i in card.id + 1..=card.id + card.points {
-                    *cards.entry(i).or_insert(0) += 1;
+impl CopyRules {
+    // Create the rules from a string like: ahead=2, multiplier=3, cap=false
+    // anything not given keeps the puzzle's rule
+    fn new(config: &str) -> Result<CopyRules, String> {
+        let mut rules = CopyRules::default();
+        for rule in config.split([' ', ',']).filter(|r| !r.is_empty()) {
+            let (name, value) = match rule.split_once('=') {
+                Some(pair) => pair,
+                None => return Err(format!("Invalid copy rule: {}", rule)),
+            };
+            match (name, value) {
+                ("ahead", v) if v.parse::<usize>().is_ok_and(|v| v > 0) => {
+                    rules.ahead = v.parse().unwrap()
+                }
+                ("multiplier", v) if v.parse::<u64>().is_ok() => {
+                    rules.multiplier = v.parse().unwrap()
                 }
+                ("cap", "true") => rules.cap = true,
+                ("cap", "false") => rules.cap = false,
+                _ => return Err(format!("Invalid copy rule: {}", rule)),
             }
         }
+        Ok(rules)
+    }
+}
+
+// Number of instances of every card, given the matches of each card in deck order. The copies a
+// card wins go to a range of later cards, so they are added where the range starts and taken off
+// where it ends, and the running sum gives the instances of each card once it is reached. Counts
+// that don't fit a u64 are an error.
+fn cascade(matches: &[u32], rules: &CopyRules) -> Result<(Vec<u64>, u64), String> {
+    let n = matches.len();
+    // kept apart rather than in one signed difference array, so the counts can use all of a u64
+    let mut added = vec![0u64; n + 1];
+    let mut expired = vec![0u64; n + 1];
+    let mut instances = Vec::with_capacity(n);
+    // copies of cards that are past the end of the deck
+    let mut overflow = 0u64;
+    let mut running = 0u64;
+    let mut total = 0u64;
+    for (i, &m) in matches.iter().enumerate() {
+        let too_many = || format!("Too many copies: card {} of the deck", i + 1);
+        running = (running - expired[i])
+            .checked_add(added[i])
+            .ok_or_else(too_many)?;
+        let count = running.checked_add(1).ok_or_else(too_many)?;
+        instances.push(count);
+        total = total.checked_add(count).ok_or_else(too_many)?;
+        if m == 0 {
+            continue;
+        }
+        let copies = count.checked_mul(rules.multiplier).ok_or_else(too_many)?;
+        let first = i.saturating_add(rules.ahead);
+        let end = first.saturating_add(m as usize);
+        if first < n {
+            added[first] = added[first].checked_add(copies).ok_or_else(too_many)?;
+            let stop = end.min(n);
+            expired[stop] = expired[stop].checked_add(copies).ok_or_else(too_many)?;
+        }
+        if !rules.cap && end > n {
+            overflow = copies
+                .checked_mul((end - first.max(n)) as u64)
+                .and_then(|past| overflow.checked_add(past))
+                .ok_or_else(too_many)?;
+        }
+    }
+    total
+        .checked_add(overflow)
+        .ok_or_else(|| "Too many copies past the end of the deck".to_string())?;
+    Ok((instances, overflow))
+}
+
+// How a card fared in the cascade: its instances at the end and the copies it got from the cards
+// before it, by card id
+struct CardTrace {
+    id: u32,
+    matching: Vec<u32>,
+    points: u64,
+    instances: u64,
+    received: Vec<(u32, u64)>,
+}
+
+// Follow the cascade card by card, so it can be checked by hand. This is O(n * matches) instead of
+// O(n), as every card remembers where its copies came from.
+fn trace(cards: &[Scratchcard], rules: &CopyRules) -> Result<(Vec<CardTrace>, u64), String> {
+    let matches = cards.iter().map(|card| card.points).collect::<Vec<_>>();
+    let (instances, overflow) = cascade(&matches, rules)?;
+    let mut traces = cards
+        .iter()
+        .zip(&instances)
+        .map(|(card, &instances)| {
+            Ok(CardTrace {
+                id: card.id,
+                matching: card.matching(),
+                points: card.checked_score()?,
+                instances,
+                received: Vec::new(),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    // only cards with matches win copies, which is where the cascade checked they fit
+    for (i, card) in cards.iter().enumerate().filter(|(_, card)| card.points > 0) {
+        let copies = instances[i] * rules.multiplier;
+        let first = i.saturating_add(rules.ahead);
+        for target in traces.iter_mut().skip(first).take(card.points as usize) {
+            target.received.push((card.id, copies));
+        }
+    }
+    Ok((traces, overflow))
+}
+
+// one line per card, like:
+// Card 4: 1 matches (84), 1 points, 8 instances, 1 from card 1, 2 from card 2, 4 from card 3
+fn trace_report(traces: &[CardTrace], overflow: u64) -> String {
+    let mut out = String::new();
+    for trace in traces {
+        out += &format!("Card {}: {} matches", trace.id, trace.matching.len());
+        if !trace.matching.is_empty() {
+            let matching = trace.matching.iter().map(|n| n.to_string());
+            out += &format!(" ({})", matching.collect::<Vec<_>>().join(" "));
+        }
+        out += &format!(", {} points, {} instances", trace.points, trace.instances);
+        for (id, copies) in &trace.received {
+            out += &format!(", {} from card {}", copies, id);
+        }
+        out.push('\n');
     }
-    cards.values().cloned().sum()
+    let total = traces.iter().map(|t| t.instances).sum::<u64>();
+    out += &format!("{} cards, {} instances", traces.len(), total + overflow);
+    if overflow > 0 {
+        out += &format!(" ({} past the end of the deck)", overflow);
+    }
+    out.push('\n');
+    out
+}
+
+// the final copy counts as a table
+fn trace_csv(traces: &[CardTrace]) -> String {
+    let mut out = "card,matches,points,instances,copies\n".to_string();
+    for trace in traces {
+        out += &format!(
+            "{},{},{},{},{}\n",
+            trace.id,
+            trace.matching.len(),
+            trace.points,
+            trace.instances,
+            trace.instances - 1
+        );
+    }
+    out
+}
+
+fn main() {
+    if fuzz::from_args("day04", fuzz_target) {
+        return;
+    }
+
+    let input = Puzzle::from_env(2023, 4).input();
+    let args = std::env::args().collect::<Vec<_>>();
+    let rules = args
+        .iter()
+        .position(|arg| arg == "--rules")
+        .and_then(|idx| args.get(idx + 1))
+        .map_or_else(|| Ok(CopyRules::default()), |config| CopyRules::new(config));
+    let rules = match rules {
+        Ok(rules) => rules,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+    if let Some(idx) = args.iter().position(|arg| arg == "--trace") {
+        let (traces, overflow) = match trace(&read_cards(&input), &rules) {
+            Ok(trace) => trace,
+            Err(error) => {
+                eprintln!("{}", error);
+                return;
+            }
+        };
+        match args.get(idx + 1) {
+            Some(file) if file.ends_with(".csv") => fs::write(file, trace_csv(&traces)).unwrap(),
+            _ => print!("{}", trace_report(&traces, overflow)),
+        }
+        return;
+    }
+    match part_1(&input) {
+        Ok(total) => println!("Part 1: {}", total),
+        Err(error) => eprintln!("Part 1: {}", error),
+    }
+    match part_2(&input, &rules) {
+        Ok(total) => println!("Part 2: {}", total),
+        Err(error) => eprintln!("Part 2: {}", error),
+    }
+}
+
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        let _ = part_1(filename);
+        let _ = part_2(filename, &CopyRules::default());
+    });
+}
+
+fn read_cards(filename: &str) -> Vec<Scratchcard> {
+    map_lines(filename, Scratchcard::new).unwrap()
+}
+
+fn part_1(filename: &str) -> Result<u64, String> {
+    fold_lines(filename, Ok(0u64), |total, line| {
+        let card = Scratchcard::new(line);
+        total?
+            .checked_add(card.checked_score()?)
+            .ok_or_else(|| "Too many points".to_string())
+    })
+    .unwrap()
+}
+
+fn part_2(filename: &str, rules: &CopyRules) -> Result<u64, String> {
+    let matches = map_lines(filename, |line| Scratchcard::new(line).points).unwrap();
+    let (instances, overflow) = cascade(&matches, rules)?;
+    Ok(instances.iter().sum::<u64>() + overflow)
 }
 
 fn parse_numbers(input: &str) -> Vec<u32> {
@@ -85,6 +312,7 @@ fn parse_numbers(input: &str) -> Vec<u32> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_parse_numbers() {
@@ -107,11 +335,182 @@ mod tests {
 
     #[test]
     fn test_part_1() {
-        assert_eq!(part_1("src/bin/day04/test_input.txt"), 13);
+        assert_eq!(part_1("src/bin/day04/test_input.txt"), Ok(13));
+    }
+
+    #[test]
+    fn test_too_many_points() {
+        let numbers = |n: u32| (1..=n).map(|x| x.to_string()).collect::<Vec<_>>().join(" ");
+        let card = |id, n| format!("Card {}: {} | {}", id, numbers(n), numbers(n));
+        assert_eq!(Scratchcard::new(&card(1, 64)).score(), Some(1 << 63));
+        assert_eq!(Scratchcard::new(&card(1, 65)).score(), None);
+        let input = format!("{}\n{}\n", card(1, 64), card(2, 65));
+        fuzz::with_input_file(input.as_bytes(), |filename| {
+            assert_eq!(part_1(filename), Err("Too many points: card 2".to_string()));
+            let cards = read_cards(filename);
+            assert_eq!(
+                trace(&cards, &CopyRules::default()).err(),
+                Some("Too many points: card 2".to_string())
+            );
+        });
+        // each card fits, their sum doesn't
+        let input = format!("{}\n{}\n", card(1, 64), card(2, 64));
+        fuzz::with_input_file(input.as_bytes(), |filename| {
+            assert_eq!(part_1(filename), Err("Too many points".to_string()));
+        });
     }
 
     #[test]
     fn test_part_2() {
-        assert_eq!(part_2("src/bin/day04/test_input.txt"), 30);
+        assert_eq!(
+            part_2("src/bin/day04/test_input.txt", &CopyRules::default()),
+            Ok(30)
+        );
+    }
+
+    // the cascade card by card, adding the copies to every card that is won
+    fn cascade_by_cards(matches: &[u32], rules: &CopyRules) -> u64 {
+        let mut instances = vec![1u64; matches.len()];
+        let mut overflow = 0;
+        for i in 0..matches.len() {
+            let copies = instances[i] * rules.multiplier;
+            for j in i + rules.ahead..i + rules.ahead + matches[i] as usize {
+                match instances.get_mut(j) {
+                    Some(count) => *count += copies,
+                    None if !rules.cap => overflow += copies,
+                    None => {}
+                }
+            }
+        }
+        instances.iter().sum::<u64>() + overflow
+    }
+
+    #[test]
+    fn test_copy_rules() {
+        assert_eq!(CopyRules::new(""), Ok(CopyRules::default()));
+        assert_eq!(
+            CopyRules::new("ahead=2, multiplier=3 cap=false"),
+            Ok(CopyRules {
+                ahead: 2,
+                multiplier: 3,
+                cap: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_copy_rule() {
+        let error = |config| CopyRules::new(config).err();
+        assert_eq!(
+            error("ahead=0"),
+            Some("Invalid copy rule: ahead=0".to_string())
+        );
+        assert_eq!(error("cap"), Some("Invalid copy rule: cap".to_string()));
+        assert_eq!(
+            error("multiplier=-1"),
+            Some("Invalid copy rule: multiplier=-1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_cascade() {
+        let matches = [4, 2, 2, 1, 0, 0];
+        let (instances, overflow) = cascade(&matches, &CopyRules::default()).unwrap();
+        assert_eq!(instances, vec![1, 2, 4, 8, 14, 1]);
+        assert_eq!(overflow, 0);
+        // the last cards win copies past the end of the deck
+        let matches = [1, 3];
+        let rules = CopyRules::new("cap=false").unwrap();
+        assert_eq!(cascade(&matches, &rules), Ok((vec![1, 2], 6)));
+    }
+
+    #[test]
+    fn test_cascade_too_many_copies() {
+        // every card wins two copies of the next one, card n has 2^n - 1 instances
+        let rules = CopyRules::new("multiplier=2").unwrap();
+        let matches = [1; 70];
+        assert_eq!(
+            cascade(&matches[..63], &rules).map(|(instances, _)| instances[62]),
+            Ok((1 << 63) - 1)
+        );
+        assert_eq!(
+            cascade(&matches, &rules),
+            Err("Too many copies: card 64 of the deck".to_string())
+        );
+        let rules = CopyRules::new(&format!("multiplier={}", u64::MAX)).unwrap();
+        assert_eq!(
+            cascade(&[1, 0], &rules),
+            Err("Too many copies: card 2 of the deck".to_string())
+        );
+        // the copies past the end of the deck fit, but not with the cards in it
+        let rules = CopyRules::new(&format!("multiplier={} cap=false", u64::MAX - 1)).unwrap();
+        assert_eq!(
+            cascade(&[0, 1], &rules),
+            Err("Too many copies past the end of the deck".to_string())
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_cascade_equals_copy_by_copy(
+            matches in prop::collection::vec(0u32..5, 0..20),
+            ahead in 1usize..4,
+            multiplier in 1u64..3,
+            cap in any::<bool>(),
+        ) {
+            let rules = CopyRules { ahead, multiplier, cap };
+            let (instances, overflow) = cascade(&matches, &rules).unwrap();
+            prop_assert_eq!(
+                instances.iter().sum::<u64>() + overflow,
+                cascade_by_cards(&matches, &rules)
+            );
+        }
+    }
+
+    #[test]
+    fn test_trace() {
+        let cards = read_cards("src/bin/day04/test_input.txt");
+        let (traces, overflow) = trace(&cards, &CopyRules::default()).unwrap();
+        assert_eq!(overflow, 0);
+        assert_eq!(traces[0].matching, vec![48, 83, 86, 17]);
+        assert_eq!(traces[3].received, vec![(1, 1), (2, 2), (3, 4)]);
+        let report = trace_report(&traces, overflow);
+        assert!(report.starts_with("Card 1: 4 matches (48 83 86 17), 8 points, 1 instances\n"));
+        assert!(report.contains(
+            "Card 4: 1 matches (84), 1 points, 8 instances, 1 from card 1, 2 from card 2, 4 from card 3\n"
+        ));
+        assert!(report.contains("Card 6: 0 matches, 0 points, 1 instances\n"));
+        assert!(report.ends_with("6 cards, 30 instances\n"));
+        let csv = trace_csv(&traces);
+        assert!(csv.starts_with("card,matches,points,instances,copies\n1,4,8,1,0\n"));
+        assert!(csv.ends_with("5,0,0,14,13\n6,0,0,1,0\n"));
+    }
+
+    #[test]
+    fn test_trace_past_the_end() {
+        let cards = vec![
+            Scratchcard::new("Card 1: 1 2 | 1 2"),
+            Scratchcard::new("Card 2: 3 | 3"),
+        ];
+        let (traces, overflow) =
+            trace(&cards, &CopyRules::new("multiplier=2, cap=false").unwrap()).unwrap();
+        assert_eq!(traces[1].received, vec![(1, 2)]);
+        assert_eq!(overflow, 8);
+        assert!(trace_report(&traces, overflow)
+            .ends_with("2 cards, 12 instances (8 past the end of the deck)\n"));
+        // the last card has no matches, so its instances times the multiplier never matter
+        let rules = CopyRules::new(&format!("multiplier={}", u64::MAX - 2)).unwrap();
+        let cards = vec![
+            Scratchcard::new("Card 1: 1 | 1"),
+            Scratchcard::new("Card 2: 2 | 3"),
+        ];
+        let (traces, _) = trace(&cards, &rules).unwrap();
+        assert_eq!(traces[1].instances, u64::MAX - 1);
+        // cards that win copies further ahead than any index
+        let rules = CopyRules::new(&format!("ahead={}", usize::MAX)).unwrap();
+        let (traces, _) = trace(&read_cards("src/bin/day04/test_input.txt"), &rules).unwrap();
+        assert!(traces
+            .iter()
+            .all(|t| t.instances == 1 && t.received.is_empty()));
     }
 }
@@ -1,184 +1,524 @@
 // --- Day 5: If You Give A Seed A Fertilizer ---
+use advent_of_code_2023::{fuzz, workspace::Puzzle};
+use std::fmt;
+use std::io::{self, BufRead};
+
+// the `destination source length` rows of a map, as they are listed
+type Rows = Vec<[u64; 3]>;
+
+// One map of the almanac, converting the `source` category into the `destination` category
+#[derive(Debug)]
+struct Layer {
+    source: String,
+    destination: String,
+    piecewise: PiecewiseMap,
+}
+
+// The seeds and the maps that lead from them to the target category, in the order they apply,
+// along with all of them composed into one
 #[derive(Default, Debug)]
 struct Almanac {
-    fertilizer: Vec<[u64; 3]>,
-    fertilizer_ranges: Vec<(u64, (u64, u64))>,
-    humidity: Vec<[u64; 3]>,
-    humidity_ranges: Vec<(u64, (u64, u64))>,
-    light: Vec<[u64; 3]>,
-    light_ranges: Vec<(u64, (u64, u64))>,
-    location: Vec<[u64; 3]>,
-    location_ranges: Vec<(u64, (u64, u64))>,
+    layers: Vec<Layer>,
+    map: PiecewiseMap,
     seed_ranges: Vec<[u64; 2]>,
     seeds: Vec<u64>,
-    soil: Vec<[u64; 3]>,
-    soil_ranges: Vec<(u64, (u64, u64))>,
-    temperature: Vec<[u64; 3]>,
-    temperature_ranges: Vec<(u64, (u64, u64))>,
-    water: Vec<[u64; 3]>,
-    water_ranges: Vec<(u64, (u64, u64))>,
+    problems: Vec<Problem>,
 }
 
 impl Almanac {
-    fn new(filename: &str) -> Almanac {
+    // the tests build almanacs they know are valid, the binary reports the errors of `with_target`
+    #[cfg(test)]
+    fn new(filename: &str, target: &str) -> Almanac {
+        Almanac::with_target(filename, target)
+            .unwrap_or_else(|error| panic!("Invalid almanac: {}", error))
+    }
+
+    // Chain the maps from seed to `target` using their `X-to-Y map:` headers, there must be exactly
+    // one way to get there. Maps that are not on the way are ignored.
+    fn with_target(filename: &str, target: &str) -> Result<Almanac, String> {
         let (seeds, maps) = parse_maps(filename);
-        let mut almanac = Almanac::default();
-        almanac.seeds = seeds;
-        almanac.find_seed_ranges();
-        for (map_name, map) in maps {
-            match map_name.as_str() {
-                "seed-to-soil map:" => almanac.soil = map,
-                "soil-to-fertilizer map:" => almanac.fertilizer = map,
-                "fertilizer-to-water map:" => almanac.water = map,
-                "water-to-light map:" => almanac.light = map,
-                "light-to-temperature map:" => almanac.temperature = map,
-                "temperature-to-humidity map:" => almanac.humidity = map,
-                "humidity-to-location map:" => almanac.location = map,
-                _ => (),
+        let problems = validate(&seeds, &maps);
+        let mut layers = Vec::new();
+        for (header, map) in maps {
+            let (source, destination) =
+                parse_header(&header).ok_or(format!("unknown header {:?}", header))?;
+            if layers
+                .iter()
+                .any(|l: &Layer| l.source == source && l.destination == destination)
+            {
+                return Err(format!("duplicate map {}-to-{}", source, destination));
             }
+            layers.push(Layer {
+                source: source.to_string(),
+                destination: destination.to_string(),
+                piecewise: PiecewiseMap::from_rows(&map),
+            });
         }
-        almanac.soil_ranges = maps_to_ranges(&almanac.soil);
-        almanac.fertilizer_ranges = maps_to_ranges(&almanac.fertilizer);
-        almanac.water_ranges = maps_to_ranges(&almanac.water);
-        almanac.light_ranges = maps_to_ranges(&almanac.light);
-        almanac.temperature_ranges = maps_to_ranges(&almanac.temperature);
-        almanac.humidity_ranges = maps_to_ranges(&almanac.humidity);
-        almanac.location_ranges = maps_to_ranges(&almanac.location);
-        almanac
+
+        let mut found = Vec::new();
+        find_paths(
+            &layers,
+            "seed",
+            target,
+            &mut vec!["seed"],
+            &mut vec![],
+            &mut found,
+        );
+        let path = match found.len() {
+            1 => found.pop().unwrap(),
+            0 => return Err(format!("no maps lead from seed to {}", target)),
+            n => return Err(format!("{} different ways lead from seed to {}", n, target)),
+        };
+        let mut layers = layers.into_iter().map(Some).collect::<Vec<_>>();
+        let mut almanac = Almanac {
+            layers: path.iter().map(|&i| layers[i].take().unwrap()).collect(),
+            seeds,
+            problems,
+            ..Default::default()
+        };
+        almanac.map = almanac
+            .layers
+            .iter()
+            .fold(PiecewiseMap::identity(), |map, layer| {
+                map.compose(&layer.piecewise)
+            });
+        almanac.find_seed_ranges();
+        Ok(almanac)
     }
 
+    // the maps are total, values no row covers stay the same
     fn find_single_seed_location(&self, seed: u64) -> u64 {
-        let soil = scan_ranges_by_seed(&self.soil, seed);
-        let fertilizer = scan_ranges_by_seed(&self.fertilizer, soil);
-        let water = scan_ranges_by_seed(&self.water, fertilizer);
-        let light = scan_ranges_by_seed(&self.light, water);
-        let temperature = scan_ranges_by_seed(&self.temperature, light);
-        let humidity = scan_ranges_by_seed(&self.humidity, temperature);
-        scan_ranges_by_seed(&self.location, humidity)
-    }
-
-    fn find_lowest_location_by_seed(&self) -> u64 {
-        let mut lowest_location = self.find_single_seed_location(self.seeds[0]);
-        for seed in &self.seeds[1..] {
-            if self.find_single_seed_location(*seed) < lowest_location {
-                lowest_location = self.find_single_seed_location(*seed);
-            }
-        }
-        lowest_location
+        self.map.apply(seed).unwrap()
+    }
+
+    // None when the almanac lists no seeds
+    fn find_lowest_location_by_seed(&self) -> Option<u64> {
+        self.seeds
+            .iter()
+            .map(|&seed| self.find_single_seed_location(seed))
+            .min()
     }
 
+    // an odd seed without a length, empty ranges and the part of a range past u64::MAX are left out,
+    // `validate` reports them
     fn find_seed_ranges(&mut self) {
-        let mut i = 0;
-        while i < &self.seeds.len() - 1 {
-            self.seed_ranges
-                .push([self.seeds[i], self.seeds[i] + self.seeds[i + 1] - 1]);
-            i += 2;
+        for pair in self.seeds.chunks_exact(2) {
+            if pair[1] > 0 {
+                self.seed_ranges
+                    .push([pair[0], pair[0].saturating_add(pair[1] - 1)]);
+            }
         }
         self.seed_ranges.sort_by(|a, b| a[0].cmp(&b[0]));
     }
 
-    fn find_lowest_location_by_ranges(&self) -> u64 {
-        let mapped_soil = map_all_ranges(&self.seed_ranges, &self.soil_ranges);
-        let mapped_fertilizer = map_all_ranges(&mapped_soil, &self.fertilizer_ranges);
-        let mapped_water = map_all_ranges(&mapped_fertilizer, &self.water_ranges);
-        let mapped_light = map_all_ranges(&mapped_water, &self.light_ranges);
-        let mapped_temperature = map_all_ranges(&mapped_light, &self.temperature_ranges);
-        let mapped_humidity = map_all_ranges(&mapped_temperature, &self.humidity_ranges);
-        let mapped_location = map_all_ranges(&mapped_humidity, &self.location_ranges);
-        mapped_location[0][0]
+    // None when no usable seed range is left, like when every range is empty
+    fn find_lowest_location_by_ranges(&self) -> Option<u64> {
+        self.map.restrict(&self.seed_ranges).lowest()
+    }
+
+    // the category the almanac converts seeds into
+    fn target(&self) -> &str {
+        self.layers.last().map_or("seed", |l| &l.destination)
+    }
+
+    // Every seed that ends up in one of the ranges, found by going back through the layers one at a
+    // time, identity gaps included
+    fn seeds_for(&self, ranges: &[[u64; 2]]) -> Vec<[u64; 2]> {
+        self.layers
+            .iter()
+            .rev()
+            .fold(merge_ranges(ranges), |ranges, layer| {
+                layer.piecewise.preimage(&ranges)
+            })
+    }
+
+    // the seeds of the seed ranges among the given ones
+    fn planted(&self, seeds: &[[u64; 2]]) -> Vec<[u64; 2]> {
+        intersect_ranges(&merge_ranges(&self.seed_ranges), seeds)
     }
 }
 
-fn maps_to_ranges(maps: &Vec<[u64; 3]>) -> Vec<(u64, (u64, u64))> {
-    let mut ranges: Vec<(u64, (u64, u64))> = Vec::new();
-    for map in maps {
-        ranges.push((map[0], (map[1], map[1] + map[2] - 1)));
+// A piece of a map: the values from `start` to `end` are shifted to begin at `destination`
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Piece {
+    start: u64,
+    end: u64,
+    destination: u64,
+}
+
+impl Piece {
+    fn apply(&self, value: u64) -> u64 {
+        self.destination + (value - self.start)
+    }
+
+    // the part of the piece between `start` and `end`
+    fn restrict(&self, start: u64, end: u64) -> Option<Piece> {
+        let (start, end) = (start.max(self.start), end.min(self.end));
+        (start <= end).then(|| Piece {
+            start,
+            end,
+            destination: self.apply(start),
+        })
+    }
+
+    // the parts of the piece that `other` does not cover
+    fn minus(&self, other: &Piece) -> Vec<Piece> {
+        if other.end < self.start || other.start > self.end {
+            return vec![*self];
+        }
+        let mut parts = Vec::new();
+        if self.start < other.start {
+            parts.extend(self.restrict(self.start, other.start - 1));
+        }
+        if self.end > other.end {
+            parts.extend(self.restrict(other.end + 1, self.end));
+        }
+        parts
     }
-    ranges.sort_by(|a, b| a.1.cmp(&b.1));
-    ranges
 }
 
-fn scan_ranges_by_seed(ranges: &Vec<[u64; 3]>, value: u64) -> u64 {
-    let mut result = value;
-    for range in ranges {
-        if value <= range[1] + range[2] - 1 && value >= range[1] {
-            result = range[0] + value - range[1];
-            break;
+// A piecewise-linear map from u64 to u64. The pieces are sorted and do not overlap, values that no
+// piece covers are not mapped at all.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct PiecewiseMap {
+    pieces: Vec<Piece>,
+}
+
+impl PiecewiseMap {
+    fn identity() -> PiecewiseMap {
+        PiecewiseMap {
+            pieces: vec![Piece {
+                start: 0,
+                end: u64::MAX,
+                destination: 0,
+            }],
+        }
+    }
+
+    // The map of the rows of an almanac map, each is [destination, source, length]. Where rows
+    // overlap the first one wins, and the values that no row covers map to themselves.
+    fn from_rows(rows: &[[u64; 3]]) -> PiecewiseMap {
+        let mut pieces: Vec<Piece> = Vec::new();
+        for &[destination, source, length] in rows {
+            // rows that would run past u64::MAX are cut short
+            let length = (length as u128)
+                .min(u64::MAX as u128 - source as u128 + 1)
+                .min(u64::MAX as u128 - destination as u128 + 1);
+            if length == 0 {
+                continue;
+            }
+            let row = Piece {
+                start: source,
+                end: (source as u128 + length - 1) as u64,
+                destination,
+            };
+            let mut free = vec![row];
+            for piece in &pieces {
+                free = free.iter().flat_map(|part| part.minus(piece)).collect();
+            }
+            pieces.extend(free);
+        }
+        pieces.sort_by_key(|p| p.start);
+
+        let mut filled = Vec::new();
+        let mut next = Some(0);
+        for piece in pieces {
+            if let Some(start) = next.filter(|&start| start < piece.start) {
+                filled.push(Piece {
+                    start,
+                    end: piece.start - 1,
+                    destination: start,
+                });
+            }
+            next = piece.end.checked_add(1);
+            filled.push(piece);
+        }
+        if let Some(start) = next {
+            filled.push(Piece {
+                start,
+                end: u64::MAX,
+                destination: start,
+            });
         }
+        PiecewiseMap::merged(filled)
+    }
+
+    // adjacent pieces that continue each other become one
+    fn merged(pieces: Vec<Piece>) -> PiecewiseMap {
+        let mut merged: Vec<Piece> = Vec::with_capacity(pieces.len());
+        for piece in pieces {
+            match merged.last_mut() {
+                Some(last)
+                    if last.end.checked_add(1) == Some(piece.start)
+                        && last.apply(last.end).checked_add(1) == Some(piece.destination) =>
+                {
+                    last.end = piece.end
+                }
+                _ => merged.push(piece),
+            }
+        }
+        PiecewiseMap { pieces: merged }
+    }
+
+    // binary search for the piece that covers the value
+    fn apply(&self, value: u64) -> Option<u64> {
+        let i = self.pieces.partition_point(|p| p.end < value);
+        self.pieces
+            .get(i)
+            .filter(|p| p.start <= value)
+            .map(|p| p.apply(value))
+    }
+
+    // this map followed by `next`
+    fn compose(&self, next: &PiecewiseMap) -> PiecewiseMap {
+        let mut pieces = Vec::new();
+        for piece in &self.pieces {
+            let (low, high) = (piece.destination, piece.apply(piece.end));
+            let first = next.pieces.partition_point(|p| p.end < low);
+            for other in next.pieces[first..].iter().take_while(|p| p.start <= high) {
+                let other = other.restrict(low, high).unwrap();
+                let start = piece.start + (other.start - low);
+                pieces.push(Piece {
+                    start,
+                    end: start + (other.end - other.start),
+                    destination: other.destination,
+                });
+            }
+        }
+        PiecewiseMap::merged(pieces)
+    }
+
+    // the map for the given inclusive ranges of values only
+    fn restrict(&self, domain: &[[u64; 2]]) -> PiecewiseMap {
+        let mut pieces = Vec::new();
+        for [start, end] in merge_ranges(domain) {
+            let first = self.pieces.partition_point(|p| p.end < start);
+            for piece in self.pieces[first..].iter().take_while(|p| p.start <= end) {
+                pieces.extend(piece.restrict(start, end));
+            }
+        }
+        PiecewiseMap::merged(pieces)
+    }
+
+    // the map back from the values this map reaches, unless two values map to the same one
+    fn invert(&self) -> Option<PiecewiseMap> {
+        let mut pieces = self
+            .pieces
+            .iter()
+            .map(|p| Piece {
+                start: p.destination,
+                end: p.apply(p.end),
+                destination: p.start,
+            })
+            .collect::<Vec<_>>();
+        pieces.sort_by_key(|p| p.start);
+        if pieces.windows(2).any(|w| w[0].end >= w[1].start) {
+            return None;
+        }
+        Some(PiecewiseMap::merged(pieces))
+    }
+
+    // The values that map into the ranges, as sorted inclusive ranges. Unlike `invert` this works
+    // when several values map to the same one.
+    fn preimage(&self, ranges: &[[u64; 2]]) -> Vec<[u64; 2]> {
+        let ranges = merge_ranges(ranges);
+        let mut preimage = Vec::new();
+        for piece in &self.pieces {
+            let (low, high) = (piece.destination, piece.apply(piece.end));
+            let first = ranges.partition_point(|r| r[1] < low);
+            for range in ranges[first..].iter().take_while(|r| r[0] <= high) {
+                let (start, end) = (range[0].max(low), range[1].min(high));
+                preimage.push([piece.start + (start - low), piece.start + (end - low)]);
+            }
+        }
+        merge_ranges(&preimage)
+    }
+
+    // the values the map reaches, as sorted inclusive ranges
+    fn image(&self) -> Vec<[u64; 2]> {
+        let ranges = self
+            .pieces
+            .iter()
+            .map(|p| [p.destination, p.apply(p.end)])
+            .collect::<Vec<_>>();
+        merge_ranges(&ranges)
+    }
+
+    fn lowest(&self) -> Option<u64> {
+        self.pieces.iter().map(|p| p.destination).min()
     }
-    result
 }
 
-fn scan_ranges_by_range(
-    seed_range: [u64; 2],
-    map_ranges: &Vec<(u64, (u64, u64))>,
-) -> Vec<[u64; 2]> {
-    let mut mapped: Vec<[u64; 2]> = Vec::new();
-    let mut start = seed_range[0];
-    let end = seed_range[1];
+// the values in both lists of sorted, merged inclusive ranges
+fn intersect_ranges(a: &[[u64; 2]], b: &[[u64; 2]]) -> Vec<[u64; 2]> {
+    let mut both = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        let (start, end) = (a[i][0].max(b[j][0]), a[i][1].min(b[j][1]));
+        if start <= end {
+            both.push([start, end]);
+        }
+        if a[i][1] < b[j][1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    both
+}
 
-    if start > map_ranges[map_ranges.len() - 1].1 .1 || end < map_ranges[0].1 .0 {
-        mapped.push([start, end]);
-        return mapped;
+// sort inclusive ranges and join the ones that overlap or touch
+fn merge_ranges(ranges: &[[u64; 2]]) -> Vec<[u64; 2]> {
+    let mut sorted = ranges.to_vec();
+    sorted.sort_unstable();
+    let mut merged: Vec<[u64; 2]> = Vec::with_capacity(sorted.len());
+    for [start, end] in sorted {
+        match merged.last_mut() {
+            Some(last) if last[1].checked_add(1).is_none_or(|next| next >= start) => {
+                last[1] = last[1].max(end)
+            }
+            _ => merged.push([start, end]),
+        }
     }
+    merged
+}
 
-    for i in 0..map_ranges.len() {
-        let min = map_ranges[i].1 .0;
-        let max = map_ranges[i].1 .1;
-        if start > max && i < map_ranges.len() - 1 {
-            continue;
-        } else if start > max && i == map_ranges.len() - 1 {
-            mapped.push([start, end]);
-            break;
-        } else if end < min {
-            mapped.push([start, end]);
-            break;
-        } else if start >= min && end <= max {
-            mapped.push([map_ranges[i].0 + start - min, map_ranges[i].0 + end - min]);
-            break;
-        } else if start >= min && end > max {
-            mapped.push([map_ranges[i].0 + start - min, map_ranges[i].0 + max - min]);
-            start = max + 1;
-            continue;
-        } else if start < min && end <= max {
-            mapped.push([start, min - 1]);
-            mapped.push([map_ranges[i].0, map_ranges[i].0 + end - min]);
-            break;
-        } else if start < min && end > max {
-            mapped.push([start, min - 1]);
-            mapped.push([map_ranges[i].0, map_ranges[i].0 + max - min]);
-            start = max + 1;
-            continue;
+// Things in the almanac that the puzzle does not allow for, the solution works around them. Rows
+// are numbered from 1 within their map.
+#[derive(Debug, PartialEq)]
+enum Problem {
+    OddSeeds(usize),
+    EmptySeedRange(u64),
+    SeedRangeOverflow(u64, u64),
+    EmptyRow(String, usize),
+    RowOverflow(String, usize),
+    Overlap(String, usize, usize, [u64; 2]),
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::OddSeeds(count) => write!(
+                f,
+                "the seeds line has {} numbers, the last one has no length",
+                count
+            ),
+            Problem::EmptySeedRange(start) => write!(f, "the seed range at {} is empty", start),
+            Problem::SeedRangeOverflow(start, length) => write!(
+                f,
+                "the seed range {} {} runs past {}",
+                start,
+                length,
+                u64::MAX
+            ),
+            Problem::EmptyRow(map, row) => write!(f, "{} row {} has length 0", map, row),
+            Problem::RowOverflow(map, row) => {
+                write!(f, "{} row {} runs past {}", map, row, u64::MAX)
+            }
+            Problem::Overlap(map, first, second, range) => write!(
+                f,
+                "{} rows {} and {} overlap on {}, row {} is used",
+                map,
+                first,
+                second,
+                format_range(range),
+                first
+            ),
+        }
+    }
+}
+
+// Check the seeds and every map of the almanac, not only the ones on the way to the target.
+// Overlaps are found by sorting the rows of a map, so an overlap with a row that is overlapped
+// itself may be reported against the longer row only.
+fn validate(seeds: &[u64], maps: &[(String, Rows)]) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    if seeds.len() % 2 == 1 {
+        problems.push(Problem::OddSeeds(seeds.len()));
+    }
+    for pair in seeds.chunks_exact(2) {
+        if pair[1] == 0 {
+            problems.push(Problem::EmptySeedRange(pair[0]));
+        } else if pair[0].checked_add(pair[1] - 1).is_none() {
+            problems.push(Problem::SeedRangeOverflow(pair[0], pair[1]));
         }
     }
 
-    if end > map_ranges[map_ranges.len() - 1].1 .1 {
-        mapped.push([start, end]);
+    for (header, rows) in maps {
+        let name = header.trim_end_matches(" map:");
+        let mut sources = Vec::new();
+        for (i, &[destination, source, length]) in rows.iter().enumerate() {
+            if length == 0 {
+                problems.push(Problem::EmptyRow(name.to_string(), i + 1));
+                continue;
+            }
+            if source.checked_add(length - 1).is_none()
+                || destination.checked_add(length - 1).is_none()
+            {
+                problems.push(Problem::RowOverflow(name.to_string(), i + 1));
+            }
+            sources.push((source, source.saturating_add(length - 1), i + 1));
+        }
+        sources.sort_unstable();
+        // the row reaching furthest so far
+        let mut furthest: Option<(u64, usize)> = None;
+        for (start, end, row) in sources {
+            if let Some((reach, other)) = furthest.filter(|&(reach, _)| reach >= start) {
+                let range = [start, end.min(reach)];
+                let (first, second) = (other.min(row), other.max(row));
+                problems.push(Problem::Overlap(name.to_string(), first, second, range));
+            }
+            if furthest.is_none_or(|(reach, _)| end > reach) {
+                furthest = Some((end, row));
+            }
+        }
     }
+    problems
+}
 
-    if mapped.is_empty() {
-        mapped = vec![[start, end]];
-    } else {
-        mapped.sort_by(|a, b| a[0].cmp(&b[0]));
+// the problems one per line, or that there are none
+fn validation_report(problems: &[Problem]) -> String {
+    if problems.is_empty() {
+        return "no problems found\n".to_string();
     }
+    problems.iter().map(|p| format!("{}\n", p)).collect()
+}
 
-    mapped
+// "seed-to-soil map:" is the map from seed to soil
+fn parse_header(header: &str) -> Option<(&str, &str)> {
+    let (source, destination) = header.strip_suffix(" map:")?.split_once("-to-")?;
+    if source.is_empty() || destination.is_empty() {
+        return None;
+    }
+    Some((source, destination))
 }
 
-fn map_all_ranges(
-    seed_ranges: &Vec<[u64; 2]>,
-    map_ranges: &Vec<(u64, (u64, u64))>,
-) -> Vec<[u64; 2]> {
-    let mut mapped: Vec<[u64; 2]> = Vec::new();
-    for seed_range in seed_ranges {
-        let mut mapped_ranges = scan_ranges_by_range(*seed_range, map_ranges);
-        mapped.append(&mut mapped_ranges);
-    }
-    mapped.sort_by(|a, b| a[0].cmp(&b[0]));
-    mapped
+// Collect every chain of layers from `from` to `to` that does not visit a category twice
+fn find_paths<'a>(
+    layers: &'a [Layer],
+    from: &str,
+    to: &str,
+    visited: &mut Vec<&'a str>,
+    path: &mut Vec<usize>,
+    found: &mut Vec<Vec<usize>>,
+) {
+    if from == to {
+        found.push(path.clone());
+        return;
+    }
+    for (i, layer) in layers.iter().enumerate() {
+        if layer.source != from || visited.contains(&layer.destination.as_str()) {
+            continue;
+        }
+        visited.push(&layer.destination);
+        path.push(i);
+        find_paths(layers, &layer.destination, to, visited, path, found);
+        path.pop();
+        visited.pop();
+    }
 }
 
-fn parse_maps(filename: &str) -> (Vec<u64>, Vec<(String, Vec<[u64; 3]>)>) {
+fn parse_maps(filename: &str) -> (Vec<u64>, Vec<(String, Rows)>) {
     let lines = advent_of_code_2023::read_lines(filename).unwrap();
     let seeds_str = lines[0].split(":").nth(1).unwrap().trim();
     let seeds = seeds_str
@@ -187,8 +527,8 @@ fn parse_maps(filename: &str) -> (Vec<u64>, Vec<(String, Vec<[u64; 3]>)>) {
         .collect::<Vec<u64>>();
     let map_lines = lines[2..].to_vec();
     let mut current: Option<String> = None;
-    let mut maps: Vec<(String, Vec<[u64; 3]>)> = Vec::new();
-    let mut map: Vec<[u64; 3]> = Vec::new();
+    let mut maps: Vec<(String, Rows)> = Vec::new();
+    let mut map: Rows = Vec::new();
 
     for line in map_lines {
         if line.trim().is_empty() {
@@ -218,24 +558,164 @@ fn parse_maps(filename: &str) -> (Vec<u64>, Vec<(String, Vec<[u64; 3]>)>) {
 }
 
 fn main() {
-    let filename = "src/bin/day05/input.txt";
-    println!("Part 1: {}", part_1(filename));
-    println!("Part 2: {:?}", part_2(filename));
+    if fuzz::from_args("day05", fuzz_target) {
+        return;
+    }
+
+    let filename = &Puzzle::from_env(2023, 5).input();
+    let args = std::env::args().collect::<Vec<_>>();
+    let target = args
+        .iter()
+        .position(|arg| arg == "--target")
+        .and_then(|idx| args.get(idx + 1))
+        .map_or("location", |target| target.as_str());
+    let almanac = match Almanac::with_target(filename, target) {
+        Ok(almanac) => almanac,
+        Err(error) => {
+            eprintln!("Invalid almanac: {}", error);
+            std::process::exit(1);
+        }
+    };
+    if args.iter().any(|arg| arg == "--validate") {
+        print!("{}", validation_report(&almanac.problems));
+        return;
+    }
+    for problem in &almanac.problems {
+        eprintln!("warning: {}", problem);
+    }
+    if args.iter().any(|arg| arg == "--strict") && !almanac.problems.is_empty() {
+        eprintln!("Refusing to solve an almanac with problems in strict mode");
+        std::process::exit(1);
+    }
+    if args.iter().any(|arg| arg == "--query") {
+        for query in io::stdin().lock().lines() {
+            println!("{}", answer_query(&almanac, &query.unwrap()));
+        }
+        return;
+    }
+    if args.iter().any(|arg| arg == "--map") {
+        print!("{}", describe_map(&almanac));
+        return;
+    }
+    match part_1(&almanac) {
+        Some(location) => println!("Part 1: {}", location),
+        None => println!("Part 1: no seeds"),
+    }
+    match part_2(&almanac) {
+        Some(location) => println!("Part 2: {}", location),
+        None => println!("Part 2: no seed ranges"),
+    }
+}
+
+fn format_range(range: &[u64; 2]) -> String {
+    if range[0] == range[1] {
+        range[0].to_string()
+    } else {
+        format!("{}..{}", range[0], range[1])
+    }
+}
+
+fn format_ranges(ranges: &[[u64; 2]]) -> String {
+    if ranges.is_empty() {
+        return "none".to_string();
+    }
+    ranges
+        .iter()
+        .map(format_range)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// A query is a seed, or a start and a length like the ranges of part 2:
+//   79            -> 82
+//   79 14         -> 46..55, 60, 82..84 (lowest 46)
+// or asks which seeds end up at a location, at a range of locations or below a location, along
+// with the seeds among them that are in the seed ranges of part 2:
+//   location 46   -> 82 (planted 82)
+//   location 46 2 -> 82..83 (planted 82..83)
+//   below 46      -> ... (planted none)
+fn answer_query(almanac: &Almanac, query: &str) -> String {
+    let words = query.split_whitespace().collect::<Vec<_>>();
+    let (reverse, numbers) = match words.first() {
+        Some(&word) if word == almanac.target() || word == "below" => (Some(word), &words[1..]),
+        _ => (None, &words[..]),
+    };
+    let numbers = numbers
+        .iter()
+        .map(|x| x.parse::<u64>())
+        .collect::<Result<Vec<_>, _>>();
+    let range = match numbers.as_deref() {
+        Ok(&[value]) => [value, value],
+        Ok(&[start, length]) if length > 0 && start.checked_add(length - 1).is_some() => {
+            [start, start + length - 1]
+        }
+        _ => return format!("Invalid query: {}", query),
+    };
+    let seeds = match (reverse, numbers.unwrap().len()) {
+        (None, 1) => return almanac.find_single_seed_location(range[0]).to_string(),
+        (None, _) => {
+            let map = almanac.map.restrict(&[range]);
+            return format!(
+                "{} (lowest {})",
+                format_ranges(&map.image()),
+                map.lowest().unwrap()
+            );
+        }
+        (Some("below"), 1) if range[0] > 0 => almanac.seeds_for(&[[0, range[0] - 1]]),
+        (Some("below"), _) => return format!("Invalid query: {}", query),
+        (Some(_), _) => almanac.seeds_for(&[range]),
+    };
+    format!(
+        "{} (planted {})",
+        format_ranges(&seeds),
+        format_ranges(&almanac.planted(&seeds))
+    )
 }
 
-fn part_1(filename: &str) -> u64 {
-    let almanac = Almanac::new(filename);
+// the composed map, one piece per line
+fn describe_map(almanac: &Almanac) -> String {
+    let target = almanac.target();
+    let mut out = String::new();
+    for piece in &almanac.map.pieces {
+        let image = [piece.destination, piece.apply(piece.end)];
+        out += &format!(
+            "seed {} -> {} {}\n",
+            format_range(&[piece.start, piece.end]),
+            target,
+            format_range(&image)
+        );
+    }
+    let one_to_one = if almanac.map.invert().is_some() {
+        "one-to-one"
+    } else {
+        "not one-to-one"
+    };
+    out += &format!("{} pieces, {}\n", almanac.map.pieces.len(), one_to_one);
+    out
+}
+
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        // invalid almanacs are reported before solving, they are not crashes
+        if let Ok(almanac) = Almanac::with_target(filename, "location") {
+            part_1(&almanac);
+            part_2(&almanac);
+        }
+    });
+}
+
+fn part_1(almanac: &Almanac) -> Option<u64> {
     almanac.find_lowest_location_by_seed()
 }
 
-fn part_2(filename: &str) -> u64 {
-    let almanac = Almanac::new(filename);
+fn part_2(almanac: &Almanac) -> Option<u64> {
     almanac.find_lowest_location_by_ranges()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_parse_maps() {
@@ -260,34 +740,110 @@ mod tests {
 
     #[test]
     fn test_almanac_new() {
-        let almanac = Almanac::new("src/bin/day05/test_input.txt");
+        let almanac = Almanac::new("src/bin/day05/test_input.txt", "location");
         assert_eq!(almanac.seeds, vec![79, 14, 55, 13]);
         assert_eq!(almanac.seed_ranges, vec![[55, 67], [79, 92]]);
-        assert_eq!(almanac.soil.len(), 2);
-        assert_eq!(almanac.fertilizer.len(), 3);
-        assert_eq!(almanac.water.len(), 4);
-        assert_eq!(almanac.light.len(), 2);
-        assert_eq!(almanac.temperature.len(), 3);
-        assert_eq!(almanac.humidity.len(), 2);
-        assert_eq!(almanac.location.len(), 2);
+        let layers = almanac
+            .layers
+            .iter()
+            .map(|l| (l.source.as_str(), l.destination.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            layers,
+            vec![
+                ("seed", "soil"),
+                ("soil", "fertilizer"),
+                ("fertilizer", "water"),
+                ("water", "light"),
+                ("light", "temperature"),
+                ("temperature", "humidity"),
+                ("humidity", "location"),
+            ]
+        );
     }
 
     #[test]
-    fn test_scan_ranges() {
-        let ranges = vec![[1, 10, 10], [11, 20, 10], [21, 30, 10]];
-        assert_eq!(scan_ranges_by_seed(&ranges, 0), 0);
-        assert_eq!(scan_ranges_by_seed(&ranges, 1), 1);
-        assert_eq!(scan_ranges_by_seed(&ranges, 10), 1);
-        assert_eq!(scan_ranges_by_seed(&ranges, 11), 2);
-        assert_eq!(scan_ranges_by_seed(&ranges, 20), 11);
-        assert_eq!(scan_ranges_by_seed(&ranges, 21), 12);
-        assert_eq!(scan_ranges_by_seed(&ranges, 30), 21);
-        assert_eq!(scan_ranges_by_seed(&ranges, 41), 41);
+    fn test_parse_header() {
+        assert_eq!(parse_header("seed-to-soil map:"), Some(("seed", "soil")));
+        assert_eq!(parse_header("seed-to-soil"), None);
+        assert_eq!(parse_header("-to-soil map:"), None);
+    }
+
+    #[test]
+    fn test_almanac_with_target() {
+        let filename = "src/bin/day05/test_input.txt";
+        let almanac = Almanac::with_target(filename, "humidity").unwrap();
+        assert_eq!(almanac.layers.len(), 6);
+        assert_eq!(almanac.layers[5].destination, "humidity");
+        // seed 13 is at humidity 35, its location is the same
+        assert_eq!(almanac.find_single_seed_location(13), 35);
+        let soil = Almanac::new(filename, "soil");
+        assert_eq!(part_1(&soil), Some(13));
+        assert_eq!(part_2(&soil), Some(57));
+        assert_eq!(part_1(&Almanac::new(filename, "seed")), Some(13));
+        assert_eq!(
+            Almanac::with_target(filename, "sunlight").unwrap_err(),
+            "no maps lead from seed to sunlight"
+        );
+    }
+
+    #[test]
+    fn test_almanac_chain_order_and_branches() {
+        // the maps are listed out of order, and the gravel map leads nowhere we want to go
+        let input = b"seeds: 1 2\n\n\
+            soil-to-location map:\n10 0 5\n\n\
+            seed-to-soil map:\n0 1 2\n\n\
+            seed-to-gravel map:\n5 0 10\n";
+        fuzz::with_input_file(input, |filename| {
+            let almanac = Almanac::with_target(filename, "location").unwrap();
+            assert_eq!(almanac.layers[0].destination, "soil");
+            assert_eq!(almanac.find_single_seed_location(1), 10);
+            assert_eq!(part_2(&almanac), Some(10));
+        });
+        let input = b"seeds: 1 2\n\n\
+            seed-to-soil map:\n0 1 2\n\n\
+            soil-to-location map:\n10 0 5\n\n\
+            seed-to-location map:\n5 0 10\n";
+        fuzz::with_input_file(input, |filename| {
+            assert_eq!(
+                Almanac::with_target(filename, "location").unwrap_err(),
+                "2 different ways lead from seed to location"
+            );
+        });
+        let input = b"seeds: 1 2\n\nseed-to-soil map:\n0 1 2\n\nseed-to-soil map:\n0 1 2\n";
+        fuzz::with_input_file(input, |filename| {
+            assert_eq!(
+                Almanac::with_target(filename, "soil").unwrap_err(),
+                "duplicate map seed-to-soil"
+            );
+        });
+    }
+
+    // the first row that covers the value maps it, like the puzzle describes it
+    fn scan_ranges_by_seed(ranges: &[[u64; 3]], value: u64) -> u64 {
+        ranges
+            .iter()
+            .find(|range| value >= range[1] && value - range[1] < range[2])
+            .map_or(value, |range| range[0] + value - range[1])
+    }
+
+    #[test]
+    fn test_piecewise_apply() {
+        let map = PiecewiseMap::from_rows(&[[1, 10, 10], [11, 20, 10], [21, 30, 10]]);
+        assert_eq!(map.apply(0), Some(0));
+        assert_eq!(map.apply(1), Some(1));
+        assert_eq!(map.apply(10), Some(1));
+        assert_eq!(map.apply(11), Some(2));
+        assert_eq!(map.apply(20), Some(11));
+        assert_eq!(map.apply(21), Some(12));
+        assert_eq!(map.apply(30), Some(21));
+        assert_eq!(map.apply(41), Some(41));
+        assert_eq!(map.apply(u64::MAX), Some(u64::MAX));
     }
 
     #[test]
     fn test_find_seed_location() {
-        let almanac = Almanac::new("src/bin/day05/test_input.txt");
+        let almanac = Almanac::new("src/bin/day05/test_input.txt", "location");
         assert_eq!(almanac.find_single_seed_location(79), 82);
         assert_eq!(almanac.find_single_seed_location(14), 43);
         assert_eq!(almanac.find_single_seed_location(55), 86);
@@ -296,76 +852,306 @@ mod tests {
 
     #[test]
     fn test_find_lowest_location() {
-        let almanac = Almanac::new("src/bin/day05/test_input.txt");
-        assert_eq!(almanac.find_lowest_location_by_seed(), 35);
+        let almanac = Almanac::new("src/bin/day05/test_input.txt", "location");
+        assert_eq!(almanac.find_lowest_location_by_seed(), Some(35));
     }
 
     #[test]
-    fn test_maps_to_ranges() {
-        let maps = vec![[1, 10, 10], [11, 20, 10], [21, 30, 10]];
-        let ranges = maps_to_ranges(&maps);
-        assert_eq!(ranges.len(), 3);
-        assert_eq!(ranges[0], (1, (10, 19)));
-        assert_eq!(ranges[1], (11, (20, 29)));
-        assert_eq!(ranges[2], (21, (30, 39)));
+    fn test_piecewise_from_rows() {
+        // the rows continue each other, so they become a single piece between the identity gaps
+        let map = PiecewiseMap::from_rows(&[[1, 10, 10], [11, 20, 10], [21, 30, 10]]);
+        let piece = |start, end, destination| Piece {
+            start,
+            end,
+            destination,
+        };
+        assert_eq!(
+            map.pieces,
+            vec![piece(0, 9, 0), piece(10, 39, 1), piece(40, u64::MAX, 40)]
+        );
+        // the first row wins where rows overlap, empty rows are skipped and a row is cut at u64::MAX
+        let map =
+            PiecewiseMap::from_rows(&[[100, 10, 10], [0, 0, 0], [200, 5, 10], [0, u64::MAX, 5]]);
+        assert_eq!(
+            map.pieces,
+            vec![
+                piece(0, 4, 0),
+                piece(5, 9, 200),
+                piece(10, 19, 100),
+                piece(20, u64::MAX - 1, 20),
+                piece(u64::MAX, u64::MAX, 0)
+            ]
+        );
     }
 
     #[test]
-    fn test_scan_ranges_by_range() {
-        let seed_range = [55, 67];
-        let map_ranges = vec![(101, (10, 19)), (11, (20, 59)), (1, (63, 66))];
-        let scanned = scan_ranges_by_range(seed_range, &map_ranges);
-        assert_eq!(scanned.len(), 4);
-        assert_eq!(scanned, [[1, 4], [46, 50], [60, 62], [67, 67]]);
+    fn test_piecewise_restrict() {
+        let map = PiecewiseMap::from_rows(&[[101, 10, 10], [11, 20, 40], [1, 63, 4]]);
+        let restricted = map.restrict(&[[55, 67]]);
+        assert_eq!(restricted.image(), [[1, 4], [46, 50], [60, 62], [67, 67]]);
+        assert_eq!(restricted.lowest(), Some(1));
+        assert_eq!(restricted.apply(54), None);
+        assert_eq!(restricted.apply(63), Some(1));
+        assert_eq!(map.restrict(&[]).lowest(), None);
     }
 
     #[test]
-    fn test_map_all_ranges() {
-        let seed_ranges = vec![[55, 67], [79, 92]];
-        let soil_map = vec![(52, (50, 97)), (50, (98, 99))];
-        let soil_ranges = map_all_ranges(&seed_ranges, &soil_map);
-        assert_eq!(soil_ranges.len(), 2);
-        assert_eq!(soil_ranges, [[57, 69], [81, 94]]);
-        let fertilizer_map = vec![(39, (0, 14)), (0, (15, 51)), (37, (52, 53))];
-        let fertilizer_ranges = map_all_ranges(&soil_ranges, &fertilizer_map);
-        assert_eq!(fertilizer_ranges.len(), 2);
-        assert_eq!(fertilizer_ranges, [[57, 69], [81, 94]]);
-        let water_map = vec![(42, (0, 6)), (57, (7, 10)), (0, (11, 52)), (49, (53, 60))];
-        let water_ranges = map_all_ranges(&fertilizer_ranges, &water_map);
-        assert_eq!(water_ranges.len(), 3);
-        assert_eq!(water_ranges, [[53, 56], [61, 69], [81, 94]]);
-        let light_map = vec![(88, (18, 24)), (18, (25, 94))];
-        let light_ranges = map_all_ranges(&water_ranges, &light_map);
-        assert_eq!(light_ranges.len(), 3);
-        assert_eq!(light_ranges, [[46, 49], [54, 62], [74, 87]]);
-        let temperature_map = vec![(81, (45, 63)), (68, (64, 76)), (45, (77, 99))];
-        let temperature_ranges = map_all_ranges(&light_ranges, &temperature_map);
-        assert_eq!(temperature_ranges.len(), 4);
-        assert_eq!(temperature_ranges, [[45, 55], [78, 80], [82, 85], [90, 98]]);
-        let humidity_map = vec![(1, (0, 68)), (0, (69, 69))];
-        let humidity_ranges = map_all_ranges(&temperature_ranges, &humidity_map);
-        assert_eq!(humidity_ranges.len(), 4);
-        assert_eq!(humidity_ranges, [[46, 56], [78, 80], [82, 85], [90, 98]]);
-        let location_map = vec![(60, (56, 92)), (56, (93, 96))];
-        let location_ranges = map_all_ranges(&humidity_ranges, &location_map);
-        assert_eq!(location_ranges.len(), 7);
+    fn test_piecewise_layers() {
+        let almanac = Almanac::new("src/bin/day05/test_input.txt", "location");
+        let mut map = PiecewiseMap::identity().restrict(&almanac.seed_ranges);
+        let expected: [&[[u64; 2]]; 7] = [
+            &[[57, 69], [81, 94]],
+            &[[57, 69], [81, 94]],
+            &[[53, 56], [61, 69], [81, 94]],
+            &[[46, 49], [54, 62], [74, 87]],
+            &[[45, 55], [78, 80], [82, 85], [90, 98]],
+            &[[46, 56], [78, 80], [82, 85], [90, 98]],
+            &[[46, 60], [82, 84], [86, 89], [94, 98]],
+        ];
+        for (layer, expected) in almanac.layers.iter().zip(expected) {
+            map = map.compose(&layer.piecewise);
+            assert_eq!(map.image(), expected, "{}", layer.destination);
+        }
+        assert_eq!(map, almanac.map.restrict(&almanac.seed_ranges));
+    }
+
+    #[test]
+    fn test_piecewise_invert() {
+        let almanac = Almanac::new("src/bin/day05/test_input.txt", "location");
+        let inverse = almanac.map.invert().unwrap();
+        assert_eq!(inverse.apply(82), Some(79));
+        assert_eq!(inverse.apply(35), Some(13));
+        assert_eq!(inverse.compose(&almanac.map), PiecewiseMap::identity());
+        // 5 and 15 both map to 5
+        assert_eq!(PiecewiseMap::from_rows(&[[0, 10, 10]]).invert(), None);
+    }
+
+    #[test]
+    fn test_merge_ranges() {
         assert_eq!(
-            location_ranges,
-            [
-                [46, 55],
-                [56, 59],
-                [60, 60],
-                [82, 84],
-                [86, 89],
-                [94, 96],
-                [97, 98]
-            ]
+            merge_ranges(&[[10, 12], [0, 3], [4, 5], [11, 20], [u64::MAX, u64::MAX]]),
+            [[0, 5], [10, 20], [u64::MAX, u64::MAX]]
         );
     }
 
     #[test]
     fn test_find_lowest_location_by_ranges() {
-        let almanac = Almanac::new("src/bin/day05/test_input.txt");
-        assert_eq!(almanac.find_lowest_location_by_ranges(), 46);
+        let almanac = Almanac::new("src/bin/day05/test_input.txt", "location");
+        assert_eq!(almanac.find_lowest_location_by_ranges(), Some(46));
+    }
+
+    fn rows_strategy() -> impl Strategy<Value = Vec<[u64; 3]>> {
+        prop::collection::vec((0u64..150, 0u64..150, 0u64..20), 0..6)
+            .prop_map(|rows| rows.into_iter().map(|(d, s, l)| [d, s, l]).collect())
+    }
+
+    proptest! {
+        #[test]
+        fn prop_composed_map_equals_layer_by_layer(
+            layers in prop::collection::vec(rows_strategy(), 1..4),
+            seeds in prop::collection::vec((0u64..200, 1u64..20), 1..5),
+        ) {
+            let seed_ranges = seeds
+                .iter()
+                .map(|(start, len)| [*start, start + len - 1])
+                .collect::<Vec<_>>();
+            let map = layers.iter().fold(PiecewiseMap::identity(), |map, rows| {
+                map.compose(&PiecewiseMap::from_rows(rows))
+            });
+            let mut by_seed = seed_ranges
+                .iter()
+                .flat_map(|range| range[0]..=range[1])
+                .map(|seed| layers.iter().fold(seed, |value, rows| scan_ranges_by_seed(rows, value)))
+                .collect::<Vec<_>>();
+            by_seed.sort_unstable();
+            by_seed.dedup();
+            let by_range = map
+                .restrict(&seed_ranges)
+                .image()
+                .iter()
+                .flat_map(|range| range[0]..=range[1])
+                .collect::<Vec<_>>();
+            prop_assert_eq!(by_range, by_seed);
+            for seed in 0..200 {
+                let value = layers.iter().fold(seed, |value, rows| scan_ranges_by_seed(rows, value));
+                prop_assert_eq!(map.apply(seed), Some(value));
+            }
+        }
+
+        #[test]
+        fn prop_preimage_equals_brute_force(
+            rows in rows_strategy(),
+            target in (0u64..200, 1u64..40),
+        ) {
+            let map = PiecewiseMap::from_rows(&rows);
+            let range = [target.0, target.0 + target.1 - 1];
+            let preimage = map
+                .preimage(&[range])
+                .iter()
+                .flat_map(|range| range[0]..=range[1])
+                .filter(|&seed| seed < 400)
+                .collect::<Vec<_>>();
+            let brute_force = (0..400)
+                .filter(|&seed| (range[0]..=range[1]).contains(&scan_ranges_by_seed(&rows, seed)))
+                .collect::<Vec<_>>();
+            prop_assert_eq!(preimage, brute_force);
+        }
+    }
+
+    #[test]
+    fn test_answer_query() {
+        let almanac = Almanac::new("src/bin/day05/test_input.txt", "location");
+        assert_eq!(answer_query(&almanac, "79"), "82");
+        assert_eq!(
+            answer_query(&almanac, "79 14"),
+            "46..55, 60, 82..84 (lowest 46)"
+        );
+        assert_eq!(
+            answer_query(&almanac, "55 13"),
+            "56..59, 86..89, 94..98 (lowest 56)"
+        );
+        assert_eq!(answer_query(&almanac, "82 1"), "46 (lowest 46)");
+        assert_eq!(answer_query(&almanac, "55 0"), "Invalid query: 55 0");
+        assert_eq!(answer_query(&almanac, "seed"), "Invalid query: seed");
+    }
+
+    #[test]
+    fn test_reverse_queries() {
+        let almanac = Almanac::new("src/bin/day05/test_input.txt", "location");
+        assert_eq!(answer_query(&almanac, "location 46"), "82 (planted 82)");
+        assert_eq!(
+            answer_query(&almanac, "location 46 2"),
+            "82..83 (planted 82..83)"
+        );
+        assert_eq!(
+            answer_query(&almanac, "below 46"),
+            "0..21, 26..43, 50..53, 70, 99 (planted none)"
+        );
+        assert_eq!(answer_query(&almanac, "location 35"), "13 (planted none)");
+        assert_eq!(answer_query(&almanac, "below 0"), "Invalid query: below 0");
+        assert_eq!(
+            answer_query(&almanac, "humidity 3"),
+            "Invalid query: humidity 3"
+        );
+        let almanac = Almanac::new("src/bin/day05/test_input.txt", "humidity");
+        assert_eq!(answer_query(&almanac, "humidity 35"), "13 (planted none)");
+    }
+
+    #[test]
+    fn test_seeds_for_checks_lowest_location() {
+        let almanac = Almanac::new("src/bin/day05/test_input.txt", "location");
+        let lowest = almanac.find_lowest_location_by_ranges().unwrap();
+        assert!(almanac
+            .planted(&almanac.seeds_for(&[[0, lowest - 1]]))
+            .is_empty());
+        assert!(!almanac
+            .planted(&almanac.seeds_for(&[[lowest, lowest]]))
+            .is_empty());
+        // going back layer by layer is the same as going back through the composed map
+        assert_eq!(
+            almanac.seeds_for(&[[0, 45]]),
+            almanac.map.preimage(&[[0, 45]])
+        );
+    }
+
+    #[test]
+    fn test_intersect_ranges() {
+        assert_eq!(
+            intersect_ranges(&[[0, 5], [10, 20]], &[[3, 12], [15, 15], [30, 40]]),
+            [[3, 5], [10, 12], [15, 15]]
+        );
+        assert!(intersect_ranges(&[[0, 5]], &[]).is_empty());
+    }
+
+    #[test]
+    fn test_describe_map() {
+        let almanac = Almanac::new("src/bin/day05/test_input.txt", "soil");
+        assert_eq!(
+            describe_map(&almanac),
+            "seed 0..49 -> soil 0..49\n\
+             seed 50..97 -> soil 52..99\n\
+             seed 98..99 -> soil 50..51\n\
+             seed 100..18446744073709551615 -> soil 100..18446744073709551615\n\
+             4 pieces, one-to-one\n"
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let almanac = Almanac::new("src/bin/day05/test_input.txt", "location");
+        assert!(almanac.problems.is_empty());
+        assert_eq!(validation_report(&almanac.problems), "no problems found\n");
+
+        let max = u64::MAX;
+        let seeds = [5, 0, max, 2, 7];
+        let maps = vec![
+            (
+                "seed-to-soil map:".to_string(),
+                vec![
+                    [0, 10, 10],
+                    [50, 15, 10],
+                    [0, 30, 0],
+                    [100, 12, 2],
+                    [0, max, 2],
+                ],
+            ),
+            ("soil-to-location map:".to_string(), vec![[max, 0, 2]]),
+        ];
+        let problems = validate(&seeds, &maps);
+        assert_eq!(
+            problems,
+            vec![
+                Problem::OddSeeds(5),
+                Problem::EmptySeedRange(5),
+                Problem::SeedRangeOverflow(max, 2),
+                Problem::EmptyRow("seed-to-soil".to_string(), 3),
+                Problem::RowOverflow("seed-to-soil".to_string(), 5),
+                Problem::Overlap("seed-to-soil".to_string(), 1, 4, [12, 13]),
+                Problem::Overlap("seed-to-soil".to_string(), 1, 2, [15, 19]),
+                Problem::RowOverflow("soil-to-location".to_string(), 1),
+            ]
+        );
+        assert_eq!(
+            validation_report(&problems[..2]),
+            "the seeds line has 5 numbers, the last one has no length\n\
+             the seed range at 5 is empty\n"
+        );
+        assert_eq!(
+            problems[5].to_string(),
+            "seed-to-soil rows 1 and 4 overlap on 12..13, row 1 is used"
+        );
+    }
+
+    #[test]
+    fn test_solve_despite_problems() {
+        // the odd seed is left out of the ranges, the overlapping row loses to the first one
+        let input = b"seeds: 10 5 0 0 3\n\nseed-to-location map:\n100 10 10\n0 12 3\n";
+        fuzz::with_input_file(input, |filename| {
+            let almanac = Almanac::new(filename, "location");
+            assert_eq!(almanac.problems.len(), 3);
+            assert_eq!(almanac.seed_ranges, vec![[10, 14]]);
+            assert_eq!(part_1(&almanac), Some(0));
+            assert_eq!(part_2(&almanac), Some(100));
+        });
+    }
+
+    #[test]
+    fn test_no_usable_seed_ranges() {
+        // an empty range and an odd seed leave nothing to plant in part 2, no seeds leave nothing
+        // at all
+        for seeds in ["79 0", "79 0 5", "7"] {
+            let input = format!("seeds: {}\n\nseed-to-location map:\n0 79 1\n", seeds);
+            fuzz::with_input_file(input.as_bytes(), |filename| {
+                let almanac = Almanac::new(filename, "location");
+                assert!(!almanac.problems.is_empty());
+                assert!(part_1(&almanac).is_some());
+                assert_eq!(part_2(&almanac), None);
+            });
+        }
+        fuzz::with_input_file(b"seeds:\n\nseed-to-location map:\n0 79 1\n", |filename| {
+            let almanac = Almanac::new(filename, "location");
+            assert_eq!(part_1(&almanac), None);
+            assert_eq!(part_2(&almanac), None);
+        });
     }
 }
@@ -1,32 +1,260 @@
 // --- Day 6: Wait For It ---
+use advent_of_code_2023::{fuzz, workspace::Puzzle};
+use std::num::IntErrorKind;
+
+// Races are kept in u128, so the concatenated race of part 2 may have up to 38 digits. A longer
+// one is not solved, part 2 reports it as an error instead.
 struct Race {
-    time: u64,
-    distance: u64,
+    time: u128,
+    distance: u128,
 }
 
 impl Race {
-    fn count_wins(&self) -> u64 {
-        let mut rising = true;
-        let mut count = 0;
-        let mut presstime = 0;
-        let mut cur = 0;
-        while rising {
-            presstime += 1;
-            let dist = presstime * (self.time - presstime);
-            if dist > cur || dist > self.distance {
-                cur = dist;
-            } else {
-                rising = false;
+    // the distance after holding the button for `press` ms, None if it does not fit in a u128 and
+    // so beats any record
+    fn distance_for(&self, press: u128) -> Option<u128> {
+        press.checked_mul(self.time - press)
+    }
+
+    fn wins(&self, press: u128) -> bool {
+        self.distance_for(press).is_none_or(|d| d > self.distance)
+    }
+
+    // The first and last press times that beat the record. The distance p * (t - p) is a parabola
+    // symmetric around t / 2, so the wins are an interval and it is enough to find the first one,
+    // which is the smaller root of p^2 - t*p + d = 0.
+    fn winning_interval(&self) -> Option<(u128, u128)> {
+        let best = self.time / 2;
+        if !self.wins(best) {
+            return None;
+        }
+        let first = match self.time.checked_mul(self.time) {
+            Some(square) => {
+                // the record is less than the best distance, so this does not underflow
+                let root = (square - 4 * self.distance).isqrt();
+                let mut first = (self.time - root) / 2;
+                // the integer root is off by at most one press either way
+                while !self.wins(first) {
+                    first += 1;
+                }
+                while first > 0 && self.wins(first - 1) {
+                    first -= 1;
+                }
+                first
             }
-            if dist > self.distance {
-                count += 1;
+            // too long for the closed form, the distance only rises up to the best press time
+            None => {
+                let (mut low, mut high) = (0, best);
+                while low < high {
+                    let mid = low + (high - low) / 2;
+                    if self.wins(mid) {
+                        high = mid;
+                    } else {
+                        low = mid + 1;
+                    }
+                }
+                low
             }
+        };
+        Some((first, self.time - first))
+    }
+}
+
+// How the boat moves: holding the button for p ms gives a speed of `acceleration` * p, at most
+// `cap`, but only once it is held for `min_press` ms. While moving the boat loses `drag` of its
+// speed every ms. The puzzle's boat has acceleration 1, no cap, no drag and no minimum.
+#[derive(Debug, PartialEq)]
+struct Physics {
+    acceleration: f64,
+    cap: Option<f64>,
+    drag: f64,
+    min_press: u128,
+}
+
+impl Default for Physics {
+    fn default() -> Self {
+        Physics {
+            acceleration: 1.0,
+            cap: None,
+            drag: 0.0,
+            min_press: 0,
         }
-        count
     }
 }
 
-fn process_race_line(line: &str) -> Vec<u64> {
+impl Physics {
+    // Create the physics from a string like: acceleration=2, cap=10, drag=0.1, min=3
+    // anything not given behaves like the puzzle's boat
+    fn new(config: &str) -> Result<Physics, String> {
+        let mut physics = Physics::default();
+        for setting in config.split([' ', ',']).filter(|s| !s.is_empty()) {
+            let (name, value) = match setting.split_once('=') {
+                Some(pair) => pair,
+                None => return Err(format!("Invalid physics: {}", setting)),
+            };
+            let number = value.parse::<f64>().ok().filter(|v| v.is_finite());
+            match (name, number) {
+                ("acceleration", Some(v)) if v >= 0.0 => physics.acceleration = v,
+                ("cap", Some(v)) if v > 0.0 => physics.cap = Some(v),
+                ("drag", Some(v)) if (0.0..=1.0).contains(&v) => physics.drag = v,
+                ("min", _) if value.parse::<u128>().is_ok() => {
+                    physics.min_press = value.parse().unwrap()
+                }
+                _ => return Err(format!("Invalid physics: {}", setting)),
+            }
+        }
+        Ok(physics)
+    }
+
+    fn distance(&self, time: u128, press: u128) -> f64 {
+        if press < self.min_press || press >= time {
+            return 0.0;
+        }
+        let mut speed = self.acceleration * press as f64;
+        if let Some(cap) = self.cap {
+            speed = speed.min(cap);
+        }
+        let moving = (time - press) as f64;
+        if self.drag == 0.0 {
+            speed * moving
+        } else {
+            // the sum of the speed over a geometric decay
+            speed * (1.0 - (1.0 - self.drag).powf(moving)) / self.drag
+        }
+    }
+
+    // The acceleration and cap when they are whole and there is no drag, so every distance is
+    // whole as well. Those are compared exactly, an f64 can't tell distances apart past 2^53.
+    fn whole(&self) -> Option<(u128, Option<u128>)> {
+        let whole = |v: f64| (v.fract() == 0.0 && v < u128::MAX as f64).then_some(v as u128);
+        if self.drag != 0.0 {
+            return None;
+        }
+        let cap = match self.cap {
+            Some(cap) => Some(whole(cap)?),
+            None => None,
+        };
+        Some((whole(self.acceleration)?, cap))
+    }
+
+    // the distance with whole physics, None if it does not fit in a u128 and so beats any record
+    fn whole_distance(&self, whole: (u128, Option<u128>), time: u128, press: u128) -> Option<u128> {
+        if press < self.min_press || press >= time {
+            return Some(0);
+        }
+        let (acceleration, cap) = whole;
+        let speed = match (acceleration.checked_mul(press), cap) {
+            (Some(speed), Some(cap)) => Some(speed.min(cap)),
+            (None, Some(cap)) => Some(cap),
+            (speed, None) => speed,
+        };
+        speed.and_then(|speed| speed.checked_mul(time - press))
+    }
+
+    fn wins(&self, race: &Race, press: u128) -> bool {
+        match self.whole() {
+            Some(whole) => self
+                .whole_distance(whole, race.time, press)
+                .is_none_or(|distance| distance > race.distance),
+            None => self.distance(race.time, press) > race.distance as f64,
+        }
+    }
+
+    // whether the distance grows from `press` to the next press time
+    fn rises(&self, time: u128, press: u128) -> bool {
+        match self.whole() {
+            Some(whole) => match self.whole_distance(whole, time, press) {
+                Some(distance) => self
+                    .whole_distance(whole, time, press + 1)
+                    .is_none_or(|next| next > distance),
+                // too long for a u128 either way, it wins and will do as the peak
+                None => false,
+            },
+            None => self.distance(time, press + 1) > self.distance(time, press),
+        }
+    }
+
+    fn winning_interval(&self, race: &Race) -> Option<(u128, u128)> {
+        if *self == Physics::default() {
+            return race.winning_interval();
+        }
+        self.solve(race)
+    }
+
+    // The speed and the distance left for it are both log-concave in the press time, so is their
+    // product and the distance rises to a single peak and then falls. That makes the wins one
+    // interval around the peak, and its ends can be found by binary search like the peak itself.
+    fn solve(&self, race: &Race) -> Option<(u128, u128)> {
+        let low = self.min_press.max(1);
+        if race.time == 0 || low >= race.time {
+            return None;
+        }
+        let high = race.time - 1;
+        let peak = first_where(low, high, |p| p == high || !self.rises(race.time, p));
+        if !self.wins(race, peak) {
+            return None;
+        }
+        let first = first_where(low, peak, |p| self.wins(race, p));
+        let last = first_where(peak, high, |p| p == high || !self.wins(race, p + 1));
+        Some((first, last))
+    }
+}
+
+// the first value from `low` to `high` that passes the test, the test has to pass for `high` and
+// keep passing once it does
+fn first_where<F: Fn(u128) -> bool>(mut low: u128, mut high: u128, test: F) -> u128 {
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if test(mid) {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    low
+}
+
+// A table of the distance for the press times of a race, like:
+// Race time 7 ms, record 9 mm, wins 2..5 (4 ways)
+//  press  distance
+//      0         0
+//      2        10 *
+// Long races show at most `MAX_TABLE_ROWS` evenly spread press times.
+const MAX_TABLE_ROWS: u128 = 100;
+
+fn distance_table(race: &Race, physics: &Physics) -> String {
+    let interval = physics.winning_interval(race);
+    let mut out = format!("Race time {} ms, record {} mm, ", race.time, race.distance);
+    out += &match interval {
+        Some((first, last)) => format!("wins {}..{} ({} ways)\n", first, last, last - first + 1),
+        None => "no wins\n".to_string(),
+    };
+    out += " press  distance\n";
+    let step = race.time.div_ceil(MAX_TABLE_ROWS).max(1);
+    // in u128, the step of a long race doesn't fit a usize
+    let mut presses = (0..=MAX_TABLE_ROWS)
+        .map(|i| i.saturating_mul(step).min(race.time))
+        .collect::<Vec<_>>();
+    if let Some((first, last)) = interval {
+        presses.extend([first, last]);
+    }
+    presses.push(race.time);
+    presses.sort_unstable();
+    presses.dedup();
+    for press in presses {
+        let distance = physics.distance(race.time, press);
+        let shown = if distance.fract() == 0.0 {
+            format!("{}", distance)
+        } else {
+            format!("{:.2}", distance)
+        };
+        let mark = if physics.wins(race, press) { " *" } else { "" };
+        out += &format!("{:>6} {:>9}{}\n", press, shown, mark);
+    }
+    out
+}
+
+fn process_race_line(line: &str) -> Vec<u128> {
     line.split(":")
         .nth(1)
         .unwrap()
@@ -36,8 +264,8 @@ fn process_race_line(line: &str) -> Vec<u64> {
 }
 
 fn parse_races(lines: Vec<String>) -> Vec<Race> {
-    let times: Vec<u64> = process_race_line(&lines[0]);
-    let distances: Vec<u64> = process_race_line(&lines[1]);
+    let times: Vec<u128> = process_race_line(&lines[0]);
+    let distances: Vec<u128> = process_race_line(&lines[1]);
     let races = times
         .into_iter()
         .zip(distances.into_iter())
@@ -47,7 +275,7 @@ fn parse_races(lines: Vec<String>) -> Vec<Race> {
     races
 }
 
-fn process_race_line_num(line: &str) -> u64 {
+fn process_race_line_num(line: &str) -> Result<u128, String> {
     let cleaned: String = line
         .split(":")
         .nth(1)
@@ -55,39 +283,93 @@ fn process_race_line_num(line: &str) -> u64 {
         .chars()
         .filter(|ch| ch.is_digit(10))
         .collect();
-    cleaned.parse().unwrap()
+    match cleaned.parse() {
+        Ok(number) => Ok(number),
+        Err(e) if *e.kind() == IntErrorKind::PosOverflow => {
+            Err(format!("Race too long for u128: {} digits", cleaned.len()))
+        }
+        Err(_) => Err(format!("No race in: {}", line)),
+    }
 }
 
-fn parse_race(lines: Vec<String>) -> Race {
-    let time = process_race_line_num(&lines[0]);
-    let distance = process_race_line_num(&lines[1]);
+fn parse_race(lines: Vec<String>) -> Result<Race, String> {
+    let time = process_race_line_num(&lines[0])?;
+    let distance = process_race_line_num(&lines[1])?;
+
+    Ok(Race { time, distance })
+}
 
-    Race { time, distance }
+fn count_wins(race: &Race, physics: &Physics) -> u128 {
+    physics
+        .winning_interval(race)
+        .map_or(0, |(first, last)| last - first + 1)
 }
 
-fn part_1(filename: &str) -> u64 {
-    let mut count: u64 = 1;
+fn part_1(filename: &str, physics: &Physics) -> u128 {
+    let mut count: u128 = 1;
     let lines = advent_of_code_2023::read_lines(filename).unwrap();
     let races = parse_races(lines);
     for race in races {
-        count *= race.count_wins();
+        count *= count_wins(&race, physics);
     }
     count
 }
 
-fn part_2(filename: &str) -> u64 {
-    let race = parse_race(advent_of_code_2023::read_lines(filename).unwrap());
-    race.count_wins()
+fn part_2(filename: &str, physics: &Physics) -> Result<u128, String> {
+    let race = parse_race(advent_of_code_2023::read_lines(filename).unwrap())?;
+    Ok(count_wins(&race, physics))
 }
 
 fn main() {
-    println!("{:?}", part_1("src/bin/day06/input.txt"));
-    println!("{:?}", part_2("src/bin/day06/input.txt"));
+    if fuzz::from_args("day06", fuzz_target) {
+        return;
+    }
+
+    let input = Puzzle::from_env(2023, 6).input();
+    let args = std::env::args().collect::<Vec<_>>();
+    let physics = match args.iter().position(|arg| arg == "--physics") {
+        Some(idx) => args
+            .get(idx + 1)
+            .ok_or_else(|| "Missing value for --physics".to_string())
+            .and_then(|config| Physics::new(config)),
+        None => Ok(Physics::default()),
+    };
+    let physics = match physics {
+        Ok(physics) => physics,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+    if args.iter().any(|arg| arg == "--table") {
+        let lines = advent_of_code_2023::read_lines(&input).unwrap();
+        for race in parse_races(lines.clone()) {
+            println!("{}", distance_table(&race, &physics));
+        }
+        match parse_race(lines) {
+            Ok(race) => print!("{}", distance_table(&race, &physics)),
+            Err(error) => eprintln!("{}", error),
+        }
+        return;
+    }
+    println!("Part 1: {:?}", part_1(&input, &physics));
+    match part_2(&input, &physics) {
+        Ok(count) => println!("Part 2: {}", count),
+        Err(error) => eprintln!("Part 2: {}", error),
+    }
+}
+
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        part_1(filename, &Physics::default());
+        let _ = part_2(filename, &Physics::default());
+    });
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_parse_races() {
@@ -106,26 +388,266 @@ mod tests {
     fn test_count_wins() {
         let lines = advent_of_code_2023::read_lines("src/bin/day06/test_input.txt");
         let races = parse_races(lines.unwrap());
-        assert_eq!(races[0].count_wins(), 4);
-        assert_eq!(races[1].count_wins(), 8);
-        assert_eq!(races[2].count_wins(), 9);
+        assert_eq!(count_wins(&races[0], &Physics::default()), 4);
+        assert_eq!(count_wins(&races[1], &Physics::default()), 8);
+        assert_eq!(count_wins(&races[2], &Physics::default()), 9);
     }
 
     #[test]
     fn test_part_1() {
-        assert_eq!(part_1("src/bin/day06/test_input.txt"), 288);
+        assert_eq!(
+            part_1("src/bin/day06/test_input.txt", &Physics::default()),
+            288
+        );
     }
 
     #[test]
     fn test_parse_race() {
         let lines = advent_of_code_2023::read_lines("src/bin/day06/test_input.txt");
-        let race = parse_race(lines.unwrap());
+        let race = parse_race(lines.unwrap()).unwrap();
         assert_eq!(race.time, 71530);
         assert_eq!(race.distance, 940200);
     }
 
+    #[test]
+    fn test_race_too_long() {
+        let lines = |time: &str| vec![format!("Time: {}", time), "Distance: 9 40".to_string()];
+        // u128::MAX has 39 digits, so every race of 38 digits fits but not every one of 39
+        let race = parse_race(lines(&"9".repeat(38))).unwrap();
+        assert_eq!(race.time, 10u128.pow(38) - 1);
+        assert_eq!(
+            parse_race(lines(&"9".repeat(39))).err(),
+            Some("Race too long for u128: 39 digits".to_string())
+        );
+        assert_eq!(
+            parse_race(lines(&format!("{0} {0}", "9".repeat(20)))).err(),
+            Some("Race too long for u128: 40 digits".to_string())
+        );
+        assert_eq!(
+            parse_race(lines("")).err(),
+            Some("No race in: Time: ".to_string())
+        );
+    }
+
     #[test]
     fn test_part_2() {
-        assert_eq!(part_2("src/bin/day06/test_input.txt"), 71503);
+        assert_eq!(
+            part_2("src/bin/day06/test_input.txt", &Physics::default()),
+            Ok(71503)
+        );
+    }
+
+    // the press times one by one
+    fn count_by_pressing(race: &Race) -> u128 {
+        (0..=race.time).filter(|&press| race.wins(press)).count() as u128
+    }
+
+    #[test]
+    fn test_winning_interval() {
+        let race = Race {
+            time: 30,
+            distance: 200,
+        };
+        assert_eq!(race.winning_interval(), Some((11, 19)));
+        // a record that can only be tied
+        let race = Race {
+            time: 6,
+            distance: 9,
+        };
+        assert_eq!(race.winning_interval(), None);
+        assert_eq!(count_wins(&race, &Physics::default()), 0);
+        let race = Race {
+            time: 0,
+            distance: 0,
+        };
+        assert_eq!(race.winning_interval(), None);
+        let race = Race {
+            time: 71530,
+            distance: 940200,
+        };
+        assert_eq!(race.winning_interval(), Some((14, 71516)));
+    }
+
+    #[test]
+    fn test_long_races() {
+        // the square of the time does not fit in a u128, the first press time still does
+        let race = Race {
+            time: u128::MAX,
+            distance: u128::MAX - 1,
+        };
+        assert_eq!(race.winning_interval(), Some((2, u128::MAX - 2)));
+        let time = 10u128.pow(30);
+        let race = Race {
+            time,
+            distance: 10u128.pow(36),
+        };
+        assert_eq!(
+            race.winning_interval(),
+            Some((10u128.pow(6) + 1, time - 10u128.pow(6) - 1))
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_count_wins_equals_pressing(time in 0u128..300, distance in 0u128..25_000) {
+            let race = Race { time, distance };
+            prop_assert_eq!(count_wins(&race, &Physics::default()), count_by_pressing(&race));
+        }
+    }
+
+    #[test]
+    fn test_physics() {
+        assert_eq!(Physics::new(""), Ok(Physics::default()));
+        assert_eq!(
+            Physics::new("acceleration=2, cap=10 drag=0.5 min=3"),
+            Ok(Physics {
+                acceleration: 2.0,
+                cap: Some(10.0),
+                drag: 0.5,
+                min_press: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_physics() {
+        let error = |config| Physics::new(config).err();
+        assert_eq!(error("drag=2"), Some("Invalid physics: drag=2".to_string()));
+        assert_eq!(error("cap=0"), Some("Invalid physics: cap=0".to_string()));
+        assert_eq!(
+            error("speed=3"),
+            Some("Invalid physics: speed=3".to_string())
+        );
+        assert_eq!(error("drag"), Some("Invalid physics: drag".to_string()));
+    }
+
+    #[test]
+    fn test_alternative_physics() {
+        let race = Race {
+            time: 7,
+            distance: 9,
+        };
+        // twice the speed: 2p(7 - p) > 9 for 1 to 6
+        let physics = Physics::new("acceleration=2").unwrap();
+        assert_eq!(physics.winning_interval(&race), Some((1, 6)));
+        // at most 4 mm/ms: 4 * (7 - p) > 9 wants p <= 4, p(7 - p) > 9 wants p >= 2
+        let physics = Physics::new("cap=4").unwrap();
+        assert_eq!(physics.winning_interval(&race), Some((2, 4)));
+        let physics = Physics::new("min=4").unwrap();
+        assert_eq!(physics.winning_interval(&race), Some((4, 5)));
+        // halving the speed every ms: 3 + 1.5 + 0.75 + 0.375 = 5.625 at best
+        let physics = Physics::new("drag=0.5").unwrap();
+        assert_eq!(physics.distance(7, 3), 5.625);
+        assert_eq!(physics.winning_interval(&race), None);
+        assert_eq!(
+            part_1(
+                "src/bin/day06/test_input.txt",
+                &Physics::new("cap=10").unwrap()
+            ),
+            0
+        );
+        assert_eq!(
+            part_2(
+                "src/bin/day06/test_input.txt",
+                &Physics::new("min=20").unwrap()
+            ),
+            Ok(71497)
+        );
+    }
+
+    #[test]
+    fn test_whole_physics_past_f64() {
+        // the record is one short of the best distance 10^18, which an f64 rounds it up to
+        let race = Race {
+            time: 2_000_000_000,
+            distance: 10u128.pow(18) - 1,
+        };
+        assert_eq!(race.distance as f64, 1e18);
+        let best = Some((1_000_000_000, 1_000_000_000));
+        assert_eq!(Physics::new("min=1").unwrap().winning_interval(&race), best);
+        let race = Race {
+            distance: 2 * 10u128.pow(18) - 1,
+            ..race
+        };
+        let physics = Physics::new("acceleration=2").unwrap();
+        assert_eq!(physics.winning_interval(&race), best);
+        let physics = Physics::new(&format!("acceleration=2, cap={}", 2 * 10u128.pow(9))).unwrap();
+        assert_eq!(physics.winning_interval(&race), best);
+        // a distance past u128::MAX beats any record
+        let physics = Physics::new("acceleration=1e30").unwrap();
+        let race = Race {
+            time: 10u128.pow(10),
+            distance: u128::MAX,
+        };
+        assert_eq!(physics.winning_interval(&race), Some((1, race.time - 1)));
+        // with drag the distances are fractional and compared as f64
+        assert_eq!(Physics::new("drag=0.5").unwrap().whole(), None);
+        assert_eq!(Physics::new("acceleration=1.5").unwrap().whole(), None);
+        assert_eq!(Physics::new("cap=2.5").unwrap().whole(), None);
+    }
+
+    #[test]
+    fn test_distance_table() {
+        let race = Race {
+            time: 7,
+            distance: 9,
+        };
+        assert_eq!(
+            distance_table(&race, &Physics::default()),
+            "Race time 7 ms, record 9 mm, wins 2..5 (4 ways)\n\
+            \x20press  distance\n\
+            \x20    0         0\n\
+            \x20    1         6\n\
+            \x20    2        10 *\n\
+            \x20    3        12 *\n\
+            \x20    4        12 *\n\
+            \x20    5        10 *\n\
+            \x20    6         6\n\
+            \x20    7         0\n"
+        );
+        let race = Race {
+            time: 71530,
+            distance: 940200,
+        };
+        let table = distance_table(&race, &Physics::new("drag=0.001").unwrap());
+        assert!(table.lines().count() <= 2 + MAX_TABLE_ROWS as usize + 3);
+        assert!(table.contains("   716 "));
+    }
+
+    #[test]
+    fn test_distance_table_long_race() {
+        for time in [1 << 70, u64::MAX as u128 + 1, u128::MAX] {
+            let race = Race { time, distance: 0 };
+            let table = distance_table(&race, &Physics::default());
+            let rows = table.lines().skip(2).collect::<Vec<_>>();
+            assert!(rows.len() <= MAX_TABLE_ROWS as usize + 3);
+            assert!(rows[rows.len() - 1]
+                .trim_start()
+                .starts_with(&time.to_string()));
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn prop_solver_equals_pressing(
+            time in 0u128..300,
+            distance in 0u128..25_000,
+            acceleration in 0u32..4,
+            cap in prop::option::of(1u32..100),
+            drag in prop::sample::select(vec![0.0, 0.01, 0.1, 0.5, 1.0]),
+            min_press in 0u128..20,
+        ) {
+            let race = Race { time, distance };
+            let physics = Physics {
+                acceleration: acceleration as f64,
+                cap: cap.map(|c| c as f64),
+                drag,
+                min_press,
+            };
+            let wins = (0..=time).filter(|&p| physics.wins(&race, p)).collect::<Vec<_>>();
+            let expected = wins.first().map(|&first| (first, *wins.last().unwrap()));
+            prop_assert_eq!(physics.solve(&race), expected);
+            prop_assert_eq!(expected.map_or(0, |(f, l)| l - f + 1), wins.len() as u128);
+        }
     }
 }
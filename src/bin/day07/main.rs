@@ -1,293 +1,725 @@
 // --- Day 7: Camel Cards ---
 use crate::Rank::HighCard;
-use lazy_static::lazy_static;
+use advent_of_code_2023::{bench, fuzz, workspace::Puzzle};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use std::cmp::{Ord, Ordering, PartialOrd};
 use std::collections::HashMap;
 
-type HandBuilder = fn(&String) -> Hand;
-
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+// Straights and flushes only happen in poker, they sit between the Camel Cards ranks where poker
+// puts them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 enum Rank {
     HighCard = 1,
     OnePair,
     TwoPairs,
     ThreeOfAKind,
+    Straight,
+    Flush,
     FullHouse,
     FourOfAKind,
+    StraightFlush,
     FiveOfAKind,
 }
 
-#[derive(Debug, Eq, PartialEq)]
-enum Labels {
-    Normal,
-    WithJoker,
+// Camel Cards ranks hands by groups of equal cards only, poker also by straights and flushes and
+// its hands are dealt with suits, like: AhKd5c5s2h 17
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Game {
+    CamelCards,
+    Poker,
+}
+
+const SUITS: &str = "cdhs";
+
+// How wild cards count when the rank of a hand is worked out
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Substitution {
+    // they join the largest group of the other cards, which always gives the best rank
+    Best,
+    // every substitution is tried, to check `Best` against
+    Exhaustive,
+    // they only count as themselves
+    Natural,
 }
 
-const CARD_LABELS: [char; 13] = [
-    '2', '3', '4', '5', '6', '7', '8', '9', 'T', 'J', 'Q', 'K', 'A',
-];
+// How hands of the same rank are ordered
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TieBreak {
+    // card by card in the order they were dealt
+    Dealt,
+    // the largest group first, like kickers in poker
+    Sorted,
+}
 
-const CARD_LABELS_WITH_JOKER: [char; 13] = [
-    'J', '2', '3', '4', '5', '6', '7', '8', '9', 'T', 'Q', 'K', 'A',
-];
+const CARD_LABELS: &str = "23456789TJQKA";
 
-lazy_static! {
-    static ref CARD_LABELS_MAP: HashMap<char, u8> = card_labels(CARD_LABELS, 2);
-    static ref CARD_LABELS_WITH_JOCKER_MAP: HashMap<char, u8> =
-        card_labels(CARD_LABELS_WITH_JOKER, 1);
+// The rules of a game of Camel Cards
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct RuleSet {
+    game: Game,
+    // card label to its strength, the weakest card has strength 0
+    strength: HashMap<char, u8>,
+    wild: Vec<char>,
+    substitution: Substitution,
+    hand_size: usize,
+    tie_break: TieBreak,
 }
 
-fn card_labels(labels: [char; 13], start_value: u8) -> HashMap<char, u8> {
-    let mut map = HashMap::new();
-    let mut value = start_value;
+impl RuleSet {
+    // the rules of part 1
+    fn normal() -> RuleSet {
+        RuleSet::new("").unwrap()
+    }
+
+    // the rules of part 2
+    fn with_joker() -> RuleSet {
+        RuleSet::new("wild=J").unwrap()
+    }
+
+    // 5-card poker, with ties broken by the largest group first
+    fn poker() -> RuleSet {
+        RuleSet::new("game=poker").unwrap()
+    }
+
+    // Create the rules from a string like: wild=J2, size=7, substitution=exhaustive, tiebreak=sorted
+    // `order=...` lists the cards from weakest to strongest, without it they are ordered like
+    // 23456789TJQKA with the wild cards moved to the bottom. Anything not given follows part 1,
+    // or standard poker after `game=poker`.
+    fn new(config: &str) -> Result<RuleSet, String> {
+        let mut order = None;
+        let mut rules = RuleSet {
+            game: Game::CamelCards,
+            strength: HashMap::new(),
+            wild: Vec::new(),
+            substitution: Substitution::Best,
+            hand_size: 5,
+            tie_break: TieBreak::Dealt,
+        };
+        for rule in config.split([' ', ',']).filter(|r| !r.is_empty()) {
+            let (name, value) = match rule.split_once('=') {
+                Some(pair) => pair,
+                None => return Err(format!("Invalid rule: {}", rule)),
+            };
+            match (name, value) {
+                ("game", "camel") => rules.game = Game::CamelCards,
+                ("game", "poker") => {
+                    rules.game = Game::Poker;
+                    rules.tie_break = TieBreak::Sorted;
+                }
+                ("order", v) if !v.is_empty() => order = Some(v.chars().collect::<Vec<_>>()),
+                ("wild", v) => rules.wild = v.chars().collect(),
+                ("substitution", "best") => rules.substitution = Substitution::Best,
+                ("substitution", "exhaustive") => rules.substitution = Substitution::Exhaustive,
+                ("substitution", "none") => rules.substitution = Substitution::Natural,
+                ("size", v) if v.parse::<usize>().is_ok_and(|v| v > 0) => {
+                    rules.hand_size = v.parse().unwrap()
+                }
+                ("tiebreak", "dealt") => rules.tie_break = TieBreak::Dealt,
+                ("tiebreak", "sorted") => rules.tie_break = TieBreak::Sorted,
+                _ => return Err(format!("Invalid rule: {}", rule)),
+            }
+        }
+        let order = order.unwrap_or_else(|| {
+            let others = CARD_LABELS.chars().filter(|c| !rules.wild.contains(c));
+            rules.wild.iter().copied().chain(others).collect()
+        });
+        for (strength, &card) in order.iter().enumerate() {
+            if rules.strength.insert(card, strength as u8).is_some() {
+                return Err(format!("Invalid rule: card {} is ordered twice", card));
+            }
+        }
+        if let Some(card) = rules.wild.iter().find(|c| !rules.strength.contains_key(c)) {
+            return Err(format!("Invalid rule: wild card {} is not ordered", card));
+        }
+        if rules.game == Game::Poker && (rules.hand_size != 5 || !rules.wild.is_empty()) {
+            return Err("Invalid rule: poker is played with 5 cards and no wild cards".to_string());
+        }
+        // the poker tables have a prime and a bit for each of the 13 labels
+        if rules.game == Game::Poker && rules.strength.len() != CARD_LABELS.len() {
+            return Err("Invalid rule: poker is played with 13 labels".to_string());
+        }
+        Ok(rules)
+    }
+
+    fn strength(&self, card: char) -> u8 {
+        self.strength[&card]
+    }
+
+    fn is_wild(&self, card: char) -> bool {
+        self.wild.contains(&card)
+    }
+
+    fn rank(&self, cards: &[char]) -> Rank {
+        match self.substitution {
+            Substitution::Natural => rank_of(&group_sizes(cards)),
+            Substitution::Best => {
+                let others = cards
+                    .iter()
+                    .copied()
+                    .filter(|&c| !self.is_wild(c))
+                    .collect::<Vec<_>>();
+                let mut sizes = group_sizes(&others);
+                let wild = (cards.len() - others.len()) as u8;
+                match sizes.first_mut() {
+                    Some(largest) => *largest += wild,
+                    None => sizes.push(wild),
+                }
+                rank_of(&sizes)
+            }
+            Substitution::Exhaustive => self.exhaustive_choices(cards).0,
+        }
+    }
+
+    // the cards the wild cards stand in for when ranking a hand, or the hand itself without any
+    fn substitute(&self, cards: &[char]) -> Vec<char> {
+        match self.substitution {
+            Substitution::Natural => cards.to_vec(),
+            Substitution::Best => {
+                // the wild cards join the largest group, the strongest one of those that tie
+                let mut counts = HashMap::new();
+                for &c in cards.iter().filter(|&&c| !self.is_wild(c)) {
+                    *counts.entry(c).or_insert(0) += 1;
+                }
+                let target = counts
+                    .into_iter()
+                    .max_by_key(|&(c, count)| (count, self.strength(c)))
+                    .map(|(c, _)| c)
+                    .or_else(|| {
+                        self.strength
+                            .keys()
+                            .copied()
+                            .filter(|&c| !self.is_wild(c))
+                            .max_by_key(|&c| self.strength(c))
+                    });
+                cards
+                    .iter()
+                    .map(|&c| match target {
+                        Some(target) if self.is_wild(c) => target,
+                        _ => c,
+                    })
+                    .collect()
+            }
+            Substitution::Exhaustive => self.exhaustive_choices(cards).1,
+        }
+    }
+
+    fn exhaustive_choices(&self, cards: &[char]) -> (Rank, Vec<char>) {
+        // only the cards in the hand and one card that is not are worth becoming
+        let mut choices = cards
+            .iter()
+            .copied()
+            .filter(|&c| !self.is_wild(c))
+            .collect::<Vec<_>>();
+        choices.sort_unstable();
+        choices.dedup();
+        let other = self
+            .strength
+            .keys()
+            .copied()
+            .filter(|&c| !self.is_wild(c) && !choices.contains(&c))
+            .max_by_key(|&c| self.strength(c));
+        choices.extend(other);
+        let mut cards = cards.to_vec();
+        self.best_substitution(&mut cards, 0, &choices)
+    }
+
+    // the best rank the wild cards from `from` on can make, with the cards that make it. Every
+    // wild card is replaced, and of the substitutions that rank the same the one with the
+    // strongest cards as dealt wins, so the cards shown for a hand don't depend on the search.
+    fn best_substitution(
+        &self,
+        cards: &mut Vec<char>,
+        from: usize,
+        choices: &[char],
+    ) -> (Rank, Vec<char>) {
+        let wild_at = (from..cards.len()).find(|&i| self.is_wild(cards[i]));
+        let Some(i) = wild_at.filter(|_| !choices.is_empty()) else {
+            return (rank_of(&group_sizes(cards)), cards.clone());
+        };
+        let wild = cards[i];
+        let best = choices
+            .iter()
+            .map(|&choice| {
+                cards[i] = choice;
+                self.best_substitution(cards, i + 1, choices)
+            })
+            .max_by_key(|found| self.substitution_key(found));
+        cards[i] = wild;
+        best.unwrap()
+    }
+
+    fn substitution_key(&self, (rank, cards): &(Rank, Vec<char>)) -> (Rank, Vec<u8>) {
+        (*rank, cards.iter().map(|&c| self.strength(c)).collect())
+    }
+
+    // the card with a strength, for explaining tie-breaks
+    fn label(&self, strength: u8) -> char {
+        self.strength
+            .iter()
+            .find(|&(_, &s)| s == strength)
+            .map_or('?', |(&c, _)| c)
+    }
+
+    // Poker ranks a hand by its groups like Camel Cards, unless it is a straight or a flush. The
+    // ace also counts low in the straight A2345.
+    fn poker_rank(&self, cards: &[char], suits: &[char]) -> Rank {
+        let flush = suits.iter().all(|&s| s == suits[0]);
+        let straight = self.straight_high(cards).is_some();
+        let sizes = group_sizes(cards);
+        match (straight, flush, sizes[0], sizes.get(1)) {
+            (true, true, _, _) => Rank::StraightFlush,
+            (_, _, 4, _) => Rank::FourOfAKind,
+            (_, _, 3, Some(2)) => Rank::FullHouse,
+            (_, true, _, _) => Rank::Flush,
+            (true, _, _, _) => Rank::Straight,
+            _ => rank_of(&sizes),
+        }
+    }
+
+    // the strength of the top card of a straight, the five of A2345
+    fn straight_high(&self, cards: &[char]) -> Option<u8> {
+        let mut strengths = cards.iter().map(|&c| self.strength(c)).collect::<Vec<_>>();
+        strengths.sort_unstable();
+        strengths.dedup();
+        let ace = self.strength.len() as u8 - 1;
+        match strengths[..] {
+            [low, .., high] if strengths.len() == 5 && high - low == 4 => Some(high),
+            [0, 1, 2, 3, high] if high == ace => Some(3),
+            _ => None,
+        }
+    }
+
+    // the strengths of the cards in the order ties are broken in
+    fn tie_break_order(&self, cards: &[char], rank: Rank) -> Vec<u8> {
+        let mut strengths = cards.iter().map(|&c| self.strength(c)).collect::<Vec<_>>();
+        if self.tie_break == TieBreak::Sorted {
+            let mut counts = HashMap::new();
+            for &strength in &strengths {
+                *counts.entry(strength).or_insert(0) += 1;
+            }
+            strengths.sort_by_key(|s| std::cmp::Reverse((counts[s], *s)));
+        }
+        if matches!(rank, Rank::Straight | Rank::StraightFlush) {
+            // A2345 ranks below 23456
+            if let Some(high) = self.straight_high(cards) {
+                strengths = (0..5).map(|i| high.saturating_sub(i)).collect();
+            }
+        }
+        strengths
+    }
 
-    for &card in labels.iter() {
-        map.insert(card, value);
-        value += 1;
+    // Poker hands as a single number that orders them like comparing their `Hand`s: the rank,
+    // then the tie-break strengths 4 bits each
+    fn poker_score(&self, cards: &[char], suits: &[char]) -> u32 {
+        let rank = self.poker_rank(cards, suits);
+        self.tie_break_order(cards, rank)
+            .iter()
+            .fold(rank as u32, |score, &s| score << 4 | s as u32)
     }
+}
+
+// Lookup tables with the score of every 5-card poker hand, indexed like Cactus Kev's evaluator:
+// flushes and hands of five different cards by the bit mask of their cards, the others by the
+// product of a prime for each card, which is the same whatever order they are dealt in.
+const PRIMES: [u32; 13] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41];
 
-    map
+struct PokerTable {
+    flush: Vec<u32>,
+    unique: Vec<u32>,
+    paired: HashMap<u32, u32>,
+}
+
+impl PokerTable {
+    // score every multiset of five cards once with the rule set
+    fn new(rules: &RuleSet) -> PokerTable {
+        let mut labels = rules.strength.iter().collect::<Vec<_>>();
+        labels.sort_by_key(|(_, &strength)| strength);
+        let labels = labels.into_iter().map(|(&c, _)| c).collect::<Vec<_>>();
+        let mut table = PokerTable {
+            flush: vec![0; 1 << 13],
+            unique: vec![0; 1 << 13],
+            paired: HashMap::new(),
+        };
+        let mut hand = Vec::with_capacity(5);
+        table.fill(rules, &labels, 0, &mut hand);
+        table
+    }
+
+    fn fill(&mut self, rules: &RuleSet, labels: &[char], from: usize, hand: &mut Vec<u8>) {
+        if hand.len() == 5 {
+            let cards = hand.iter().map(|&s| labels[s as usize]).collect::<Vec<_>>();
+            let mask = hand.iter().fold(0, |mask, &s| mask | 1 << s);
+            if (mask as u32).count_ones() == 5 {
+                // the suits of the off-suit hand may not be all the same
+                self.flush[mask] = rules.poker_score(&cards, &['s'; 5]);
+                self.unique[mask] = rules.poker_score(&cards, &['s', 's', 's', 's', 'h']);
+            } else {
+                let product = hand.iter().map(|&s| PRIMES[s as usize]).product();
+                self.paired.insert(
+                    product,
+                    rules.poker_score(&cards, &['s', 's', 's', 's', 'h']),
+                );
+            }
+            return;
+        }
+        for strength in from..labels.len() {
+            // at most four cards of a kind
+            if hand.len() >= 4 && hand[hand.len() - 4] == strength as u8 {
+                continue;
+            }
+            hand.push(strength as u8);
+            self.fill(rules, labels, strength, hand);
+            hand.pop();
+        }
+    }
+
+    // the strengths and suits (0 to 3) of the five cards
+    fn score(&self, cards: &[(u8, u8); 5]) -> u32 {
+        let mask = cards.iter().fold(0, |mask, &(s, _)| mask | 1 << s);
+        if cards.iter().all(|&(_, suit)| suit == cards[0].1) {
+            self.flush[mask]
+        } else if (mask as u32).count_ones() == 5 {
+            self.unique[mask]
+        } else {
+            let product = cards
+                .iter()
+                .map(|&(s, _)| PRIMES[s as usize])
+                .product::<u32>();
+            self.paired[&product]
+        }
+    }
+}
+
+// the sizes of the groups of equal cards, largest first
+fn group_sizes(cards: &[char]) -> Vec<u8> {
+    let mut counts: HashMap<char, u8> = HashMap::new();
+    for card in cards {
+        *counts.entry(*card).or_insert(0) += 1;
+    }
+    let mut sizes = counts.into_values().collect::<Vec<_>>();
+    sizes.sort_unstable_by(|a, b| b.cmp(a));
+    sizes
+}
+
+fn rank_of(sizes: &[u8]) -> Rank {
+    match_counts(
+        sizes.first().copied().unwrap_or(0),
+        sizes.get(1).copied().unwrap_or(0),
+    )
 }
 
 #[derive(Debug, Eq, PartialEq)]
-struct Hand {
+struct Hand<'a> {
     bid: u32,
     cards: Vec<char>,
-    labels: Labels,
+    // only poker hands have suits
+    suits: Vec<char>,
+    rules: &'a RuleSet,
     rank: Rank,
 }
 
-impl PartialOrd<Self> for Hand {
+impl PartialOrd<Self> for Hand<'_> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl Ord for Hand {
+// hands are compared under the rules of the left one
+impl Ord for Hand<'_> {
     fn cmp(&self, other: &Self) -> Ordering {
-        return if self.labels == Labels::WithJoker && other.labels == Labels::WithJoker {
-            self.cmp_with_joker(other)
-        } else {
-            self.cmp_without_joker(other)
-        };
+        self.rank.cmp(&other.rank).then_with(|| {
+            self.rules
+                .tie_break_order(&self.cards, self.rank)
+                .cmp(&self.rules.tie_break_order(&other.cards, other.rank))
+        })
     }
 }
 
-impl Hand {
-    fn new(cards: Vec<char>, bid: u32, labels: Labels) -> Hand {
+impl<'a> Hand<'a> {
+    fn new(cards: Vec<char>, bid: u32, rules: &'a RuleSet) -> Hand<'a> {
         let hand = Hand {
             bid,
             cards,
-            labels,
+            suits: Vec::new(),
+            rules,
             rank: HighCard,
         };
 
         hand
     }
 
-    fn get_rank(&mut self) -> Rank {
-        let mut counts: HashMap<char, u8> = HashMap::new();
-        for card in &self.cards {
-            let count = counts.entry(*card).or_insert(0);
-            *count += 1;
-        }
-        let mut counts: Vec<(char, u8)> = counts.into_iter().collect();
-        counts.sort_by(|a, b| b.1.cmp(&a.1));
-        let count = counts.get(0).map(|(_, count)| *count).unwrap_or(0);
-        let second_count = counts.get(1).map(|(_, count)| *count).unwrap_or(0);
-        match_counts(count, second_count)
-    }
-
-    fn get_rank_with_joker(&mut self) -> Rank {
-        let (counts, joker_count) = get_card_counts(&self.cards);
-        let mut counts: Vec<(char, u8)> = counts.into_iter().collect();
-        counts.sort_by(|a, b| b.1.cmp(&a.1));
-        let count = counts.get(0).map(|(_, count)| *count).unwrap_or(0);
-        let second_count = counts.get(1).map(|(_, count)| *count).unwrap_or(0);
-        let mut rank = HighCard;
-        match count + joker_count {
-            5 => rank = Rank::FiveOfAKind,
-            4 => rank = Rank::FourOfAKind,
-            3 => {
-                if second_count == 2 {
-                    rank = Rank::FullHouse
-                } else {
-                    rank = Rank::ThreeOfAKind
-                }
-            }
-            2 => {
-                if second_count == 2 {
-                    rank = Rank::TwoPairs
-                } else {
-                    rank = Rank::OnePair
-                }
-            }
-            _ => (),
-        }
-
-        rank
-    }
-
-    fn cmp_with_joker(&self, other: &Self) -> Ordering {
-        match self.rank.cmp(&other.rank) {
-            Ordering::Equal => {
-                for i in 0..self.cards.len() {
-                    if CARD_LABELS_WITH_JOCKER_MAP[&self.cards[i]]
-                        > CARD_LABELS_WITH_JOCKER_MAP[&other.cards[i]]
-                    {
-                        return Ordering::Greater;
-                    } else if CARD_LABELS_WITH_JOCKER_MAP[&self.cards[i]]
-                        < CARD_LABELS_WITH_JOCKER_MAP[&other.cards[i]]
-                    {
-                        return Ordering::Less;
-                    }
-                }
-                Ordering::Equal
-            }
-            _ => self.rank.cmp(&other.rank),
+    // the strengths and suits of a poker hand for `PokerTable`
+    fn poker_cards(&self) -> [(u8, u8); 5] {
+        let mut cards = [(0, 0); 5];
+        for (i, (&card, &suit)) in self.cards.iter().zip(&self.suits).enumerate() {
+            cards[i] = (self.rules.strength(card), SUITS.find(suit).unwrap() as u8);
         }
+        cards
     }
 
-    fn cmp_without_joker(&self, other: &Self) -> Ordering {
-        match self.rank.cmp(&other.rank) {
-            Ordering::Equal => {
-                for i in 0..self.cards.len() {
-                    if CARD_LABELS_MAP[&self.cards[i]] > CARD_LABELS_MAP[&other.cards[i]] {
-                        return Ordering::Greater;
-                    } else if CARD_LABELS_MAP[&self.cards[i]] < CARD_LABELS_MAP[&other.cards[i]] {
-                        return Ordering::Less;
-                    }
-                }
-                Ordering::Equal
-            }
-            _ => self.rank.cmp(&other.rank),
+    fn get_rank(&self) -> Rank {
+        match self.rules.game {
+            Game::CamelCards => self.rules.rank(&self.cards),
+            Game::Poker => self.rules.poker_rank(&self.cards, &self.suits),
         }
     }
 }
 
+// groups of five or more are all five of a kind, so larger hands rank like the best five cards
 fn match_counts(count: u8, second_count: u8) -> Rank {
-    let mut rank = HighCard;
-    match count {
-        5 => rank = Rank::FiveOfAKind,
-        4 => rank = Rank::FourOfAKind,
-        3 => {
-            if second_count == 2 {
-                rank = Rank::FullHouse
-            } else {
-                rank = Rank::ThreeOfAKind
-            }
-        }
-        2 => {
-            if second_count == 2 {
-                rank = Rank::TwoPairs
-            } else {
-                rank = Rank::OnePair
-            }
-        }
-        _ => (),
+    match (count, second_count) {
+        (5.., _) => Rank::FiveOfAKind,
+        (4, _) => Rank::FourOfAKind,
+        (3, 2..) => Rank::FullHouse,
+        (3, _) => Rank::ThreeOfAKind,
+        (2, 2) => Rank::TwoPairs,
+        (2, _) => Rank::OnePair,
+        _ => HighCard,
     }
-    rank
 }
 
-fn get_card_counts(cards: &Vec<char>) -> (HashMap<char, u8>, u8) {
-    let mut counts: HashMap<char, u8> = HashMap::new();
-    let mut joker_count = 0;
-
-    for card in cards {
-        if *card == 'J' {
-            joker_count += 1;
-            continue;
-        } else {
-            let count = counts.entry(*card).or_insert(0);
-            *count += 1;
+fn hand_from_string<'a>(line: &str, rules: &'a RuleSet) -> Hand<'a> {
+    let split_line: Vec<&str> = line.split_whitespace().collect();
+    let mut cards: Vec<char> = split_line[0].chars().collect();
+    let mut suits = Vec::new();
+    if rules.game == Game::Poker {
+        // every card is followed by its suit
+        suits = cards.iter().skip(1).step_by(2).copied().collect();
+        cards = cards.iter().step_by(2).copied().collect();
+        // there is one of every card in the deck
+        let mut dealt = cards.iter().zip(&suits).collect::<Vec<_>>();
+        dealt.sort_unstable();
+        dealt.dedup();
+        if suits.len() != cards.len()
+            || suits.iter().any(|&s| !SUITS.contains(s))
+            || dealt.len() != cards.len()
+        {
+            panic!("Invalid hand: {}", split_line[0]);
         }
     }
-    (counts, joker_count)
-}
-
-fn hand_from_string(line: &String, labels: Labels) -> Hand {
-    let split_line: Vec<&str> = line.split_whitespace().collect();
-    let cards: Vec<char> = split_line[0].chars().collect();
+    if cards.len() != rules.hand_size || cards.iter().any(|c| !rules.strength.contains_key(c)) {
+        panic!("Invalid hand: {}", split_line[0]);
+    }
     let bid: u32 = split_line[1].parse().unwrap();
-    Hand::new(cards, bid, labels)
-}
-
-fn build_hand(line: &String) -> Hand {
-    let mut hand = hand_from_string(line, Labels::Normal);
-    hand.rank = hand.get_rank();
-
+    let mut hand = Hand::new(cards, bid, rules);
+    hand.suits = suits;
     hand
 }
 
-fn build_hand_with_joker(line: &String) -> Hand {
-    let mut hand = hand_from_string(line, Labels::WithJoker);
-    hand.rank = hand.get_rank_with_joker();
+fn build_hand<'a>(line: &str, rules: &'a RuleSet) -> Hand<'a> {
+    let mut hand = hand_from_string(line, rules);
+    hand.rank = hand.get_rank();
 
     hand
 }
 
-fn hands_from_input(filename: &str, build: HandBuilder) -> Vec<Hand> {
-    advent_of_code_2023::read_lines(filename)
-        .unwrap()
-        .iter()
-        .map(build)
-        .collect()
+fn hands_from_input<'a>(filename: &str, rules: &'a RuleSet) -> Vec<Hand<'a>> {
+    advent_of_code_2023::map_lines(filename, |line| build_hand(line, rules)).unwrap()
 }
 
-fn find_total_winnings(filename: &str, build: HandBuilder) -> u64 {
+fn find_total_winnings(filename: &str, rules: &RuleSet) -> u64 {
     let mut total = 0;
-    let mut hands = hands_from_input(filename, build);
+    let mut hands = hands_from_input(filename, rules);
     hands.sort();
     // iterate with index to get the highest ranked hand
     for (i, hand) in hands.iter().enumerate() {
-        total += (hand.bid * (i + 1) as u32) as u64;
+        total += hand.bid as u64 * (i + 1) as u64;
     }
     total
 }
 
+// the hands in the order they win in, with the cards the wild cards stood in for and why each
+// hand beats the one before it. Under sorted tie-breaks the card positions are those after
+// sorting.
+fn explain(hands: &mut [Hand]) -> String {
+    hands.sort();
+    let mut report = String::new();
+    for (i, hand) in hands.iter().enumerate() {
+        report += &format!(
+            "{}. {} bid {}: {:?}",
+            i + 1,
+            hand_label(hand),
+            hand.bid,
+            hand.rank
+        );
+        if hand.rules.game == Game::CamelCards {
+            let substituted = hand.rules.substitute(&hand.cards);
+            if substituted != hand.cards {
+                report += &format!(" as {}", substituted.iter().collect::<String>());
+            }
+        }
+        if let Some(previous) = i.checked_sub(1).map(|i| &hands[i]) {
+            report += &format!(", {}", tie_break(previous, hand));
+        }
+        report.push('\n');
+    }
+    report
+}
+
+fn hand_label(hand: &Hand) -> String {
+    if hand.suits.is_empty() {
+        return hand.cards.iter().collect();
+    }
+    hand.cards
+        .iter()
+        .zip(&hand.suits)
+        .flat_map(|(&card, &suit)| [card, suit])
+        .collect()
+}
+
+// why `hand` sorts after `previous`: its rank, or the first card that differs
+fn tie_break(previous: &Hand, hand: &Hand) -> String {
+    let name = hand_label(previous);
+    if hand.rank != previous.rank {
+        return format!("beats {} by rank", name);
+    }
+    let rules = hand.rules;
+    let ours = rules.tie_break_order(&hand.cards, hand.rank);
+    let theirs = rules.tie_break_order(&previous.cards, previous.rank);
+    match ours.iter().zip(&theirs).position(|(a, b)| a != b) {
+        Some(i) => format!(
+            "beats {} at card {} ({} > {})",
+            name,
+            i + 1,
+            rules.label(ours[i]),
+            rules.label(theirs[i])
+        ),
+        None => format!("ties with {}", name),
+    }
+}
+
+// random poker hands in the suited input format
+fn generate_poker_hands(count: usize) -> Vec<String> {
+    let mut rng = StdRng::seed_from_u64(7);
+    let deck = CARD_LABELS
+        .chars()
+        .flat_map(|card| SUITS.chars().map(move |suit| format!("{}{}", card, suit)))
+        .collect::<Vec<_>>();
+    (0..count)
+        .map(|_| {
+            let mut hand = String::with_capacity(14);
+            let mut dealt = Vec::with_capacity(5);
+            while dealt.len() < 5 {
+                let card = rng.gen_range(0..deck.len());
+                if !dealt.contains(&card) {
+                    dealt.push(card);
+                    hand += &deck[card];
+                }
+            }
+            hand += &format!(" {}", rng.gen_range(1..1000));
+            hand
+        })
+        .collect()
+}
+
+fn bench(millions: usize) {
+    let rules = RuleSet::poker();
+    let lines = generate_poker_hands(millions * 1_000_000);
+    let hands = lines
+        .iter()
+        .map(|line| build_hand(line, &rules))
+        .collect::<Vec<_>>();
+    let encoded = hands
+        .iter()
+        .map(|hand| hand.poker_cards())
+        .collect::<Vec<_>>();
+    bench::measure("lookup tables", None, || PokerTable::new(&rules));
+    let table = PokerTable::new(&rules);
+    println!("{} hands", hands.len());
+    bench::measure("rank by groups", None, || {
+        hands
+            .iter()
+            .map(|hand| rules.poker_score(&hand.cards, &hand.suits) as u64)
+            .sum::<u64>()
+    });
+    bench::measure("rank by lookup", None, || {
+        encoded
+            .iter()
+            .map(|cards| table.score(cards) as u64)
+            .sum::<u64>()
+    });
+}
+
 fn main() {
-    println!(
-        "Part 1: {}",
-        find_total_winnings("src/bin/day07/input.txt", build_hand)
-    );
-    println!(
-        "Part 2: {}",
-        find_total_winnings("src/bin/day07/input.txt", build_hand_with_joker)
-    );
+    if fuzz::from_args("day07", fuzz_target) {
+        return;
+    }
+    if let Some(millions) = bench::from_args(1) {
+        bench(millions);
+        return;
+    }
+
+    let input = Puzzle::from_env(2023, 7).input();
+    let args = std::env::args().collect::<Vec<_>>();
+    let explaining = args.iter().any(|arg| arg == "--explain");
+    let report = |name: &str, rules: &RuleSet| {
+        if explaining {
+            print!("{}", explain(&mut hands_from_input(&input, rules)));
+        }
+        println!("{}: {}", name, find_total_winnings(&input, rules));
+    };
+    if let Some(config) = args
+        .iter()
+        .position(|arg| arg == "--rules")
+        .and_then(|idx| args.get(idx + 1))
+    {
+        match RuleSet::new(config) {
+            Ok(rules) => report("Winnings", &rules),
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    report("Part 1", &RuleSet::normal());
+    report("Part 2", &RuleSet::with_joker());
+}
+
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        find_total_winnings(filename, &RuleSet::normal());
+        find_total_winnings(filename, &RuleSet::with_joker());
+    });
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_hand_new() {
-        let hand = Hand::new(vec!['2', '3', '4', '5', '6'], 0, Labels::Normal);
+        let normal = RuleSet::normal();
+        let hand = Hand::new(vec!['2', '3', '4', '5', '6'], 0, &normal);
         assert_eq!(hand.cards, vec!['2', '3', '4', '5', '6']);
         assert_eq!(hand.rank, Rank::HighCard);
     }
 
     #[test]
     fn test_hand_get_rank() {
-        let mut hand = Hand::new(vec!['2', '3', '4', '5', '6'], 0, Labels::Normal);
+        let normal = RuleSet::normal();
+        let mut hand = Hand::new(vec!['2', '3', '4', '5', '6'], 0, &normal);
         assert_eq!(hand.get_rank(), Rank::HighCard);
-        hand = Hand::new(vec!['2', '2', '2', '2', '6'], 0, Labels::Normal);
+        hand = Hand::new(vec!['2', '2', '2', '2', '6'], 0, &normal);
         assert_eq!(hand.get_rank(), Rank::FourOfAKind);
-        hand = Hand::new(vec!['2', '2', '2', '6', '6'], 0, Labels::Normal);
+        hand = Hand::new(vec!['2', '2', '2', '6', '6'], 0, &normal);
         assert_eq!(hand.get_rank(), Rank::FullHouse);
-        hand = Hand::new(vec!['2', '2', '2', '3', '6'], 0, Labels::Normal);
+        hand = Hand::new(vec!['2', '2', '2', '3', '6'], 0, &normal);
         assert_eq!(hand.get_rank(), Rank::ThreeOfAKind);
-        hand = Hand::new(vec!['2', '2', '3', '3', '6'], 0, Labels::Normal);
+        hand = Hand::new(vec!['2', '2', '3', '3', '6'], 0, &normal);
         assert_eq!(hand.get_rank(), Rank::TwoPairs);
-        hand = Hand::new(vec!['2', '2', '3', '4', '6'], 0, Labels::Normal);
+        hand = Hand::new(vec!['2', '2', '3', '4', '6'], 0, &normal);
         assert_eq!(hand.get_rank(), Rank::OnePair);
-        hand = Hand::new(vec!['2', '3', '4', '5', '6'], 0, Labels::Normal);
+        hand = Hand::new(vec!['2', '3', '4', '5', '6'], 0, &normal);
         assert_eq!(hand.get_rank(), Rank::HighCard);
     }
 
     #[test]
     fn test_hand_cmp() {
-        let mut hands = hands_from_input("src/bin/day07/test_input.txt", build_hand);
+        let rules = RuleSet::normal();
+        let mut hands = hands_from_input("src/bin/day07/test_input.txt", &rules);
         hands.sort();
         assert_eq!(hands[0].cards, vec!['3', '2', 'T', '3', 'K']);
         assert_eq!(hands[1].cards, vec!['K', 'T', 'J', 'J', 'T']);
@@ -298,7 +730,8 @@ mod tests {
 
     #[test]
     fn test_hands_from_input() {
-        let hands = hands_from_input("src/bin/day07/test_input.txt", build_hand);
+        let rules = RuleSet::normal();
+        let hands = hands_from_input("src/bin/day07/test_input.txt", &rules);
         assert_eq!(hands[0].cards, vec!['3', '2', 'T', '3', 'K']);
         assert_eq!(hands[1].cards, vec!['T', '5', '5', 'J', '5']);
         assert_eq!(hands[2].cards, vec!['K', 'K', '6', '7', '7']);
@@ -319,46 +752,48 @@ mod tests {
     #[test]
     fn test_part_1() {
         assert_eq!(
-            find_total_winnings("src/bin/day07/test_input.txt", build_hand),
+            find_total_winnings("src/bin/day07/test_input.txt", &RuleSet::normal()),
             6440
         );
     }
 
     #[test]
     fn test_hand_get_rank_with_joker() {
-        let mut hand = Hand::new(vec!['2', '3', '4', '5', '6'], 0, Labels::WithJoker);
-        assert_eq!(hand.get_rank_with_joker(), Rank::HighCard);
-        hand = Hand::new(vec!['2', '2', '2', '2', '6'], 0, Labels::WithJoker);
-        assert_eq!(hand.get_rank_with_joker(), Rank::FourOfAKind);
-        hand = Hand::new(vec!['2', '2', '2', '6', '6'], 0, Labels::WithJoker);
-        assert_eq!(hand.get_rank_with_joker(), Rank::FullHouse);
-        hand = Hand::new(vec!['2', '2', '2', '3', '6'], 0, Labels::WithJoker);
-        assert_eq!(hand.get_rank_with_joker(), Rank::ThreeOfAKind);
-        hand = Hand::new(vec!['2', '2', '3', '3', '6'], 0, Labels::WithJoker);
-        assert_eq!(hand.get_rank_with_joker(), Rank::TwoPairs);
-        hand = Hand::new(vec!['2', '2', '3', '4', '6'], 0, Labels::WithJoker);
-        assert_eq!(hand.get_rank_with_joker(), Rank::OnePair);
-        hand = Hand::new(vec!['2', '3', '4', '5', '6'], 0, Labels::WithJoker);
-        assert_eq!(hand.get_rank_with_joker(), Rank::HighCard);
-        hand = Hand::new(vec!['J', '2', '3', '4', '5'], 0, Labels::WithJoker);
-        assert_eq!(hand.get_rank_with_joker(), Rank::OnePair);
-        hand = Hand::new(vec!['J', '2', '2', '2', '2'], 0, Labels::WithJoker);
-        assert_eq!(hand.get_rank_with_joker(), Rank::FiveOfAKind);
-        hand = Hand::new(vec!['J', '2', '2', '2', '6'], 0, Labels::WithJoker);
-        assert_eq!(hand.get_rank_with_joker(), Rank::FourOfAKind);
-        hand = Hand::new(vec!['J', '2', '2', '6', '6'], 0, Labels::WithJoker);
-        assert_eq!(hand.get_rank_with_joker(), Rank::FullHouse);
-        hand = Hand::new(vec!['J', '2', '2', '3', '6'], 0, Labels::WithJoker);
-        assert_eq!(hand.get_rank_with_joker(), Rank::ThreeOfAKind);
-        hand = Hand::new(vec!['J', '2', '3', '3', '6'], 0, Labels::WithJoker);
-        assert_eq!(hand.get_rank_with_joker(), Rank::ThreeOfAKind);
-        hand = Hand::new(vec!['T', '5', '5', 'J', '5'], 0, Labels::WithJoker);
-        assert_eq!(hand.get_rank_with_joker(), Rank::FourOfAKind);
+        let joker = RuleSet::with_joker();
+        let mut hand = Hand::new(vec!['2', '3', '4', '5', '6'], 0, &joker);
+        assert_eq!(hand.get_rank(), Rank::HighCard);
+        hand = Hand::new(vec!['2', '2', '2', '2', '6'], 0, &joker);
+        assert_eq!(hand.get_rank(), Rank::FourOfAKind);
+        hand = Hand::new(vec!['2', '2', '2', '6', '6'], 0, &joker);
+        assert_eq!(hand.get_rank(), Rank::FullHouse);
+        hand = Hand::new(vec!['2', '2', '2', '3', '6'], 0, &joker);
+        assert_eq!(hand.get_rank(), Rank::ThreeOfAKind);
+        hand = Hand::new(vec!['2', '2', '3', '3', '6'], 0, &joker);
+        assert_eq!(hand.get_rank(), Rank::TwoPairs);
+        hand = Hand::new(vec!['2', '2', '3', '4', '6'], 0, &joker);
+        assert_eq!(hand.get_rank(), Rank::OnePair);
+        hand = Hand::new(vec!['2', '3', '4', '5', '6'], 0, &joker);
+        assert_eq!(hand.get_rank(), Rank::HighCard);
+        hand = Hand::new(vec!['J', '2', '3', '4', '5'], 0, &joker);
+        assert_eq!(hand.get_rank(), Rank::OnePair);
+        hand = Hand::new(vec!['J', '2', '2', '2', '2'], 0, &joker);
+        assert_eq!(hand.get_rank(), Rank::FiveOfAKind);
+        hand = Hand::new(vec!['J', '2', '2', '2', '6'], 0, &joker);
+        assert_eq!(hand.get_rank(), Rank::FourOfAKind);
+        hand = Hand::new(vec!['J', '2', '2', '6', '6'], 0, &joker);
+        assert_eq!(hand.get_rank(), Rank::FullHouse);
+        hand = Hand::new(vec!['J', '2', '2', '3', '6'], 0, &joker);
+        assert_eq!(hand.get_rank(), Rank::ThreeOfAKind);
+        hand = Hand::new(vec!['J', '2', '3', '3', '6'], 0, &joker);
+        assert_eq!(hand.get_rank(), Rank::ThreeOfAKind);
+        hand = Hand::new(vec!['T', '5', '5', 'J', '5'], 0, &joker);
+        assert_eq!(hand.get_rank(), Rank::FourOfAKind);
     }
 
     #[test]
     fn test_hands_from_input_with_joker() {
-        let hands = hands_from_input("src/bin/day07/test_input.txt", build_hand_with_joker);
+        let rules = RuleSet::with_joker();
+        let hands = hands_from_input("src/bin/day07/test_input.txt", &rules);
         assert_eq!(hands[0].cards, vec!['3', '2', 'T', '3', 'K']);
         assert_eq!(hands[1].cards, vec!['T', '5', '5', 'J', '5']);
         assert_eq!(hands[2].cards, vec!['K', 'K', '6', '7', '7']);
@@ -378,7 +813,8 @@ mod tests {
 
     #[test]
     fn test_hands_with_joker_cmp() {
-        let mut hands = hands_from_input("src/bin/day07/test_input.txt", build_hand_with_joker);
+        let rules = RuleSet::with_joker();
+        let mut hands = hands_from_input("src/bin/day07/test_input.txt", &rules);
         hands.sort();
         assert_eq!(hands[0].cards, vec!['3', '2', 'T', '3', 'K']);
         assert_eq!(hands[1].cards, vec!['K', 'K', '6', '7', '7']);
@@ -390,8 +826,301 @@ mod tests {
     #[test]
     fn test_part_2() {
         assert_eq!(
-            find_total_winnings("src/bin/day07/test_input.txt", build_hand_with_joker),
+            find_total_winnings("src/bin/day07/test_input.txt", &RuleSet::with_joker()),
             5905
         );
     }
+
+    fn rank(rules: &RuleSet, cards: &str) -> Rank {
+        rules.rank(&cards.chars().collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_rule_set_new() {
+        let rules =
+            RuleSet::new("wild=J2, size=7 substitution=exhaustive tiebreak=sorted").unwrap();
+        assert_eq!(rules.wild, vec!['J', '2']);
+        assert_eq!(rules.strength('J'), 0);
+        assert_eq!(rules.strength('2'), 1);
+        assert_eq!(rules.strength('3'), 2);
+        assert_eq!(rules.strength('A'), 12);
+        assert_eq!(rules.hand_size, 7);
+        assert_eq!(rules.substitution, Substitution::Exhaustive);
+        assert_eq!(rules.tie_break, TieBreak::Sorted);
+        // wild cards keep their place when the order is given
+        let rules = RuleSet::new("order=23456789TQKAJ wild=J").unwrap();
+        assert_eq!(rules.strength('J'), 12);
+        assert_eq!(RuleSet::with_joker().strength('J'), 0);
+    }
+
+    #[test]
+    fn test_invalid_rule_set() {
+        let error = |config| RuleSet::new(config).err();
+        assert_eq!(
+            error("order=23456789TJQKA wild=X"),
+            Some("Invalid rule: wild card X is not ordered".to_string())
+        );
+        assert_eq!(
+            error("order=23456789TJQKAA"),
+            Some("Invalid rule: card A is ordered twice".to_string())
+        );
+        assert_eq!(error("size=0"), Some("Invalid rule: size=0".to_string()));
+        assert_eq!(error("wild"), Some("Invalid rule: wild".to_string()));
+    }
+
+    #[test]
+    fn test_wild_ranks() {
+        let rules = RuleSet::new("wild=J2").unwrap();
+        assert_eq!(rank(&rules, "J2345"), Rank::ThreeOfAKind);
+        assert_eq!(rank(&rules, "J2JJ2"), Rank::FiveOfAKind);
+        assert_eq!(rank(&rules, "2J3KK"), Rank::FourOfAKind);
+        // jokers that are the weakest card but count as themselves
+        let rules = RuleSet::new("wild=J substitution=none").unwrap();
+        assert_eq!(rank(&rules, "JJ234"), Rank::OnePair);
+        assert_eq!(rules.strength('J'), 0);
+    }
+
+    #[test]
+    fn test_seven_card_hands() {
+        let rules = RuleSet::new("size=7").unwrap();
+        assert_eq!(rank(&rules, "AAKKKQQ"), Rank::FullHouse);
+        assert_eq!(rank(&rules, "AAKKQQ2"), Rank::TwoPairs);
+        assert_eq!(rank(&rules, "AAAAAAK"), Rank::FiveOfAKind);
+        assert_eq!(rank(&rules, "2345678"), Rank::HighCard);
+        let hand = build_hand("AAKKKQ2 10", &rules);
+        assert_eq!(hand.rank, Rank::FullHouse);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid hand: AAKKK")]
+    fn test_hand_of_wrong_size() {
+        build_hand("AAKKK 10", &RuleSet::new("size=7").unwrap());
+    }
+
+    #[test]
+    fn test_tie_break() {
+        let dealt = RuleSet::normal();
+        let first = build_hand("23332 1", &dealt);
+        let second = build_hand("33322 1", &dealt);
+        assert_eq!(first.cmp(&second), Ordering::Less);
+        // sorted both are 33322
+        let sorted = RuleSet::new("tiebreak=sorted").unwrap();
+        let first = build_hand("23332 1", &sorted);
+        let second = build_hand("33322 1", &sorted);
+        assert_eq!(first.cmp(&second), Ordering::Equal);
+        let first = build_hand("KK677 1", &sorted);
+        let second = build_hand("KTJJT 1", &sorted);
+        assert_eq!(first.cmp(&second), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_exhaustive_winnings() {
+        let rules = RuleSet::new("wild=J substitution=exhaustive").unwrap();
+        assert_eq!(
+            find_total_winnings("src/bin/day07/test_input.txt", &rules),
+            5905
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_best_substitution_is_exhaustive(
+            cards in prop::collection::vec(prop::sample::select(CARD_LABELS.chars().collect::<Vec<_>>()), 5..=7),
+            wild in prop::sample::select(vec!["J", "J2", "JQK"]),
+        ) {
+            let config = format!("wild={} size={}", wild, cards.len());
+            let best = RuleSet::new(&config).unwrap();
+            let exhaustive = RuleSet::new(&(config + " substitution=exhaustive")).unwrap();
+            prop_assert_eq!(best.rank(&cards), exhaustive.rank(&cards));
+            // the substituted cards rank as well with nothing wild
+            for rules in [&best, &exhaustive] {
+                let substituted = rules.substitute(&cards);
+                prop_assert!(!substituted.iter().any(|&c| rules.is_wild(c)));
+                prop_assert_eq!(rank_of(&group_sizes(&substituted)), rules.rank(&cards));
+            }
+        }
+    }
+
+    #[test]
+    fn test_explain() {
+        let rules = RuleSet::with_joker();
+        let mut hands = hands_from_input("src/bin/day07/test_input.txt", &rules);
+        assert_eq!(
+            explain(&mut hands),
+            "1. 32T3K bid 765: OnePair\n\
+             2. KK677 bid 28: TwoPairs, beats 32T3K by rank\n\
+             3. T55J5 bid 684: FourOfAKind as T5555, beats KK677 by rank\n\
+             4. QQQJA bid 483: FourOfAKind as QQQQA, beats T55J5 at card 1 (Q > T)\n\
+             5. KTJJT bid 220: FourOfAKind as KTTTT, beats QQQJA at card 1 (K > Q)\n"
+        );
+        let rules = RuleSet::normal();
+        let mut hands = hands_from_input("src/bin/day07/test_input.txt", &rules);
+        assert!(explain(&mut hands)
+            .contains("3. KK677 bid 28: TwoPairs, beats KTJJT at card 2 (K > T)\n"));
+    }
+
+    #[test]
+    fn test_explain_ties_and_suits() {
+        let rules = RuleSet::new("wild=J").unwrap();
+        assert_eq!(rules.substitute(&['J'; 5]), vec!['A'; 5]);
+        let exhaustive = RuleSet::new("wild=J substitution=exhaustive").unwrap();
+        assert_eq!(exhaustive.substitute(&['J'; 5]), vec!['A'; 5]);
+        assert_eq!(
+            exhaustive.substitute(&['2', 'J', 'J', 'J', 'J']),
+            vec!['2', '2', '2', '2', '2']
+        );
+        // the card that is not in the hand is never a wild one
+        let wild_first = RuleSet::new("wild=2 substitution=exhaustive").unwrap();
+        assert_eq!(wild_first.substitute(&['2'; 5]), vec!['A'; 5]);
+        assert_eq!(
+            rules.substitute(&['2', '2', 'K', 'K', 'J']),
+            vec!['2', '2', 'K', 'K', 'K']
+        );
+        let mut hands = vec![build_hand("2345J 1", &rules), build_hand("2345J 2", &rules)];
+        assert!(
+            explain(&mut hands).ends_with("2. 2345J bid 2: OnePair as 23455, ties with 2345J\n")
+        );
+
+        let rules = RuleSet::poker();
+        let mut hands = vec![
+            poker_hand(&rules, "AhKhQhJhTh"),
+            poker_hand(&rules, "Ac2d3h4s5c"),
+        ];
+        assert_eq!(
+            explain(&mut hands),
+            "1. Ac2d3h4s5c bid 1: Straight\n\
+             2. AhKhQhJhTh bid 1: StraightFlush, beats Ac2d3h4s5c by rank\n"
+        );
+    }
+
+    fn poker_hand<'a>(rules: &'a RuleSet, cards: &str) -> Hand<'a> {
+        build_hand(&format!("{} 1", cards), rules)
+    }
+
+    #[test]
+    fn test_poker_ranks() {
+        let rules = RuleSet::poker();
+        let ranks = [
+            ("AhKhQhJhTh", Rank::StraightFlush),
+            ("9c9d9h9s2c", Rank::FourOfAKind),
+            ("KsKhKd2c2s", Rank::FullHouse),
+            ("2h4h6h8hTh", Rank::Flush),
+            ("Ah2c3d4s5h", Rank::Straight),
+            ("9hTcJdQsKh", Rank::Straight),
+            ("7c7d7h2s3c", Rank::ThreeOfAKind),
+            ("7c7d2h2s3c", Rank::TwoPairs),
+            ("7c7d4h2s3c", Rank::OnePair),
+            ("QhKcAd2s3c", Rank::HighCard),
+        ];
+        for (cards, rank) in ranks {
+            let hand = poker_hand(&rules, cards);
+            assert_eq!(hand.rank, rank, "{}", cards);
+        }
+        assert_eq!(
+            poker_hand(&rules, "AhKd5c5s2h").suits,
+            vec!['h', 'd', 'c', 's', 'h']
+        );
+    }
+
+    #[test]
+    fn test_poker_cmp() {
+        let rules = RuleSet::poker();
+        let wheel = poker_hand(&rules, "Ah2c3d4s5h");
+        let six_high = poker_hand(&rules, "2h3c4d5s6h");
+        assert!(wheel < six_high);
+        // kickers after the pair
+        let aces = poker_hand(&rules, "2hAcAd5s9h");
+        let aces_king = poker_hand(&rules, "AhAsKd3s2c");
+        assert!(aces < aces_king);
+        assert!(poker_hand(&rules, "2h4h6h8hTh") < poker_hand(&rules, "2c2d2h3s3c"));
+        assert_eq!(
+            poker_hand(&rules, "AhKhQhJh9h").cmp(&poker_hand(&rules, "AsKsQsJs9s")),
+            Ordering::Equal
+        );
+        let input = b"AhKhQhJhTh 1\n2h3c4d5s6h 10\nAh2c3d4s5h 100\n";
+        fuzz::with_input_file(input, |filename| {
+            assert_eq!(find_total_winnings(filename, &rules), 100 + 2 * 10 + 3);
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid hand: AhKhQhJhT")]
+    fn test_poker_hand_without_suit() {
+        poker_hand(&RuleSet::poker(), "AhKhQhJhT");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid hand: AsAhAdAcAs")]
+    fn test_poker_hand_with_repeated_card() {
+        // four aces are fine, the fifth one is a card that was already dealt
+        poker_hand(&RuleSet::poker(), "AsAhAdAc2s");
+        poker_hand(&RuleSet::poker(), "AsAhAdAcAs");
+    }
+
+    #[test]
+    fn test_poker_order_too_long() {
+        assert_eq!(
+            RuleSet::new("game=poker order=123456789TJQKA").err(),
+            Some("Invalid rule: poker is played with 13 labels".to_string())
+        );
+    }
+
+    #[test]
+    fn test_poker_with_wild_cards() {
+        assert_eq!(
+            RuleSet::new("game=poker wild=J").err(),
+            Some("Invalid rule: poker is played with 5 cards and no wild cards".to_string())
+        );
+    }
+
+    #[test]
+    fn test_poker_table() {
+        let rules = RuleSet::poker();
+        let table = PokerTable::new(&rules);
+        assert_eq!(table.paired.len(), 4888);
+        let mut scores = table
+            .flush
+            .iter()
+            .chain(&table.unique)
+            .chain(table.paired.values())
+            .filter(|&&score| score > 0)
+            .collect::<Vec<_>>();
+        scores.sort_unstable();
+        scores.dedup();
+        // the number of different 5-card poker hands
+        assert_eq!(scores.len(), 7462);
+
+        for line in generate_poker_hands(20_000) {
+            let hand = build_hand(&line, &rules);
+            assert_eq!(
+                table.score(&hand.poker_cards()),
+                rules.poker_score(&hand.cards, &hand.suits),
+                "{}",
+                line
+            );
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn prop_poker_score_orders_like_hands(
+            first in prop::sample::subsequence((0..52u8).collect::<Vec<_>>(), 5),
+            second in prop::sample::subsequence((0..52u8).collect::<Vec<_>>(), 5),
+        ) {
+            let rules = RuleSet::poker();
+            let deal = |cards: &[u8]| {
+                cards
+                    .iter()
+                    .map(|&c| format!("{}{}", &CARD_LABELS[(c / 4) as usize..][..1], &SUITS[(c % 4) as usize..][..1]))
+                    .collect::<String>()
+            };
+            let first = poker_hand(&rules, &deal(&first));
+            let second = poker_hand(&rules, &deal(&second));
+            let scores = (
+                rules.poker_score(&first.cards, &first.suits),
+                rules.poker_score(&second.cards, &second.suits),
+            );
+            prop_assert_eq!(first.cmp(&second), scores.0.cmp(&scores.1));
+        }
+    }
 }
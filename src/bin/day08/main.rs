@@ -1,6 +1,6 @@
 // --- Day 8: Haunted Wasteland ---
 
-use advent_of_code_2023::{lcm, read_lines};
+use advent_of_code_2023::{fuzz, lcm, read_lines, workspace::Puzzle};
 use std::collections::HashMap;
 
 #[derive(Debug, PartialEq)]
@@ -114,8 +114,20 @@ fn part_2(filename: &str) -> i64 {
 }
 
 fn main() {
-    println!("Part 1: {}", part_1("src/bin/day08/input.txt"));
-    println!("Part 2: {}", part_2("src/bin/day08/input.txt"));
+    if fuzz::from_args("day08", fuzz_target) {
+        return;
+    }
+
+    let input = Puzzle::from_env(2023, 8).input();
+    println!("Part 1: {}", part_1(&input));
+    println!("Part 2: {}", part_2(&input));
+}
+
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        part_1(filename);
+        part_2(filename);
+    });
 }
 
 #[cfg(test)]
@@ -1,4 +1,6 @@
 // --- Day 9: Mirage Maintenance ---
+use advent_of_code_2023::{fuzz, workspace::Puzzle};
+
 struct History(Vec<Vec<i64>>);
 
 impl History {
@@ -45,8 +47,8 @@ impl History {
         self.add(next);
     }
 
-    fn extrapolate(line: String) -> i64 {
-        let mut h = History::new(&line);
+    fn extrapolate(line: &str) -> i64 {
+        let mut h = History::new(line);
         h.generate_sequence();
         h.add_to_array(h.len() - 1, 0);
         for i in (0..h.len() - 1).rev() {
@@ -56,8 +58,8 @@ impl History {
         *h.last_of_nth(0).unwrap()
     }
 
-    fn extrapolate_backwards(line: String) -> i64 {
-        let mut h = History::new(&line);
+    fn extrapolate_backwards(line: &str) -> i64 {
+        let mut h = History::new(line);
         h.generate_sequence();
         h.insert_into_array(h.len() - 1, 0);
         for i in (0..h.len() - 1).rev() {
@@ -82,13 +84,9 @@ fn diff_array(arr: &[i64]) -> Vec<i64> {
 
 fn history_processor<F1>(filename: &str, extrapolate: F1) -> i64
 where
-    F1: Fn(String) -> i64,
+    F1: Fn(&str) -> i64,
 {
-    advent_of_code_2023::read_lines(filename)
-        .unwrap()
-        .into_iter()
-        .map(extrapolate)
-        .sum()
+    advent_of_code_2023::fold_lines(filename, 0, |sum, line| sum + extrapolate(line)).unwrap()
 }
 
 fn part_1(filename: &str) -> i64 {
@@ -100,13 +98,26 @@ fn part_2(filename: &str) -> i64 {
 }
 
 fn main() {
-    println!("part 1: {}", part_1("src/bin/day09/input.txt"));
-    println!("part 2: {}", part_2("src/bin/day09/input.txt"));
+    if fuzz::from_args("day09", fuzz_target) {
+        return;
+    }
+
+    let input = Puzzle::from_env(2023, 9).input();
+    println!("part 1: {}", part_1(&input));
+    println!("part 2: {}", part_2(&input));
+}
+
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        part_1(filename);
+        part_2(filename);
+    });
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_check_all_zeros() {
@@ -158,13 +169,13 @@ mod tests {
 
     #[test]
     fn test_extrapolate() {
-        let res = History::extrapolate("0 3 6 9 12 15".to_string());
+        let res = History::extrapolate("0 3 6 9 12 15");
         assert_eq!(res, 18);
 
-        let res = History::extrapolate("1 3 6 10 15 21".to_string());
+        let res = History::extrapolate("1 3 6 10 15 21");
         assert_eq!(res, 28);
 
-        let res = History::extrapolate("10 13 16 21 30 45".to_string());
+        let res = History::extrapolate("10 13 16 21 30 45");
         assert_eq!(res, 68);
     }
 
@@ -175,13 +186,13 @@ mod tests {
 
     #[test]
     fn test_extrapolate_backwards() {
-        let res = History::extrapolate_backwards("0 3 6 9 12 15".to_string());
+        let res = History::extrapolate_backwards("0 3 6 9 12 15");
         assert_eq!(res, -3);
 
-        let res = History::extrapolate_backwards("1 3 6 10 15 21".to_string());
+        let res = History::extrapolate_backwards("1 3 6 10 15 21");
         assert_eq!(res, 0);
 
-        let res = History::extrapolate_backwards("10 13 16 21 30 45".to_string());
+        let res = History::extrapolate_backwards("10 13 16 21 30 45");
         assert_eq!(res, 5);
     }
 
@@ -189,4 +200,31 @@ mod tests {
     fn test_part_2() {
         assert_eq!(part_2("src/bin/day09/test_input.txt"), 2);
     }
+
+    fn polynomial(coefficients: &[i64], x: i64) -> i64 {
+        coefficients.iter().rev().fold(0, |acc, c| acc * x + c)
+    }
+
+    proptest! {
+        #[test]
+        fn prop_extrapolation_is_exact_for_polynomials(
+            coefficients in prop::collection::vec(-20i64..20, 1..5),
+            extra in 1usize..5,
+        ) {
+            // a polynomial of degree d needs d + 2 values for the differences to reach zero
+            let len = coefficients.len() + extra;
+            let line = (0..len as i64)
+                .map(|x| polynomial(&coefficients, x).to_string())
+                .collect::<Vec<_>>()
+                .join(" ");
+            prop_assert_eq!(
+                History::extrapolate(&line),
+                polynomial(&coefficients, len as i64)
+            );
+            prop_assert_eq!(
+                History::extrapolate_backwards(&line),
+                polynomial(&coefficients, -1)
+            );
+        }
+    }
 }
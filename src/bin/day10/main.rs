@@ -1,4 +1,5 @@
 // --- Day 10: Pipe Maze ---
+use advent_of_code_2023::{fuzz, workspace::Puzzle};
 use std::time::Instant;
 
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
@@ -245,17 +246,29 @@ fn part_2(filename: &str) -> i32 {
 }
 
 fn main() {
+    if fuzz::from_args("day10", fuzz_target) {
+        return;
+    }
+
+    let input = Puzzle::from_env(2023, 10).input();
     let now = Instant::now();
-    println!("Part 1: {}", part_1("src/bin/day10/input.txt"));
+    println!("Part 1: {}", part_1(&input));
     let elapsed = now.elapsed();
     println!("Elapsed time is: {:?}", elapsed);
 
     let now = Instant::now();
-    println!("Part 2: {}", part_2("src/bin/day10/input.txt"));
+    println!("Part 2: {}", part_2(&input));
     let elapsed = now.elapsed();
     println!("Elapsed time is: {:?}", elapsed);
 }
 
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        part_1(filename);
+        part_2(filename);
+    });
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
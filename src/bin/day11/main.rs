@@ -1,4 +1,6 @@
 // --- Day 11: Cosmic Expansion ---
+use advent_of_code_2023::{fuzz, workspace::Puzzle};
+
 #[derive(Debug, PartialEq)]
 enum CellType {
     Empty,
@@ -161,14 +163,20 @@ fn sum_of_all_shortest_paths(filename: &str, factor: i64) -> i64 {
 }
 
 fn main() {
-    println!(
-        "Part 1: {}",
-        sum_of_all_shortest_paths("src/bin/day11/input.txt", 2)
-    );
-    println!(
-        "Part 2: {}",
-        sum_of_all_shortest_paths("src/bin/day11/input.txt", 1000000)
-    );
+    if fuzz::from_args("day11", fuzz_target) {
+        return;
+    }
+
+    let input = Puzzle::from_env(2023, 11).input();
+    println!("Part 1: {}", sum_of_all_shortest_paths(&input, 2));
+    println!("Part 2: {}", sum_of_all_shortest_paths(&input, 1000000));
+}
+
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        sum_of_all_shortest_paths(filename, 2);
+        sum_of_all_shortest_paths(filename, 1000000);
+    });
 }
 
 #[cfg(test)]
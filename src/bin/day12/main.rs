@@ -1,26 +1,51 @@
 // --- Day 12: Hot Springs ---
 
+use advent_of_code_2023::{fuzz, workspace::Puzzle, LineReader};
 use itertools::Itertools;
 use rayon::iter::{IntoParallelIterator, ParallelBridge, ParallelIterator};
 use std::{collections::HashMap, time::Instant};
 
 #[allow(dead_code)]
-const INPUT: &'static str = include_str!("./input.txt");
+const INPUT: &str = "src/bin/day12/input.txt";
 #[allow(dead_code)]
-const SAMPLE: &'static str = include_str!("./test_input.txt");
+const SAMPLE: &str = "src/bin/day12/test_input.txt";
+// number of rows handed to the parallel solver at once
+const CHUNK_LINES: usize = 4096;
 
 fn main() {
+    if fuzz::from_args("day12", fuzz_target) {
+        return;
+    }
+
+    let input = Puzzle::from_env(2023, 12).input();
     let time_start = Instant::now();
-    let res1 = process_part_1(INPUT);
+    let res1 = process_file(&input, process_part_1);
     println!("Part 1: {:?}", res1);
     println!("Time: {}μs", time_start.elapsed().as_micros());
 
     let time_start = Instant::now();
-    let res2 = process_part_2(INPUT);
+    let res2 = process_file(&input, process_part_2);
     println!("Part 2: {:?}", res2);
     println!("Time: {}μs", time_start.elapsed().as_micros());
 }
 
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        process_file(filename, process_part_1);
+        process_file(filename, process_part_2);
+    });
+}
+
+// stream the file in chunks of rows so large inputs are solved in bounded memory
+fn process_file(filename: &str, process: fn(&str) -> usize) -> usize {
+    let mut reader = LineReader::open(filename).unwrap();
+    let mut sum = 0;
+    while let Some(chunk) = reader.next_chunk(CHUNK_LINES).unwrap() {
+        sum += process(chunk);
+    }
+    sum
+}
+
 fn process_part_1(input: &str) -> usize {
     input
         .lines()
@@ -146,21 +171,21 @@ mod tests {
 
     #[test]
     fn part_1_sample() {
-        assert_eq!(process_part_1(SAMPLE), 21)
+        assert_eq!(process_file(SAMPLE, process_part_1), 21)
     }
 
     #[test]
     fn part_1_input() {
-        assert_eq!(process_part_1(INPUT), 6_949)
+        assert_eq!(process_file(INPUT, process_part_1), 6_949)
     }
 
     #[test]
     fn part_2_sample_1() {
-        assert_eq!(process_part_2(SAMPLE), 525_152)
+        assert_eq!(process_file(SAMPLE, process_part_2), 525_152)
     }
 
     #[test]
     fn part_2_input() {
-        assert_eq!(process_part_2(INPUT), 51_456_609_952_403)
+        assert_eq!(process_file(INPUT, process_part_2), 51_456_609_952_403)
     }
 }
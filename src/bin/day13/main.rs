@@ -1,18 +1,30 @@
 // --- Day 13: Point of Incidence ---
 
-use advent_of_code_2023::read_lines;
+use advent_of_code_2023::{fuzz, workspace::Puzzle, LineReader};
+use std::io::BufRead;
 
 fn main() {
-    println!("Part 1: {}", part_1("src/bin/day13/input.txt"));
+    if fuzz::from_args("day13", fuzz_target) {
+        return;
+    }
+
+    let input = Puzzle::from_env(2023, 13).input();
+    println!("Part 1: {}", part_1(&input));
+}
+
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        part_1(filename);
+    });
 }
 
 fn part_1(filename: &str) -> u64 {
-    let input = read_lines(filename).unwrap();
-    let mirrors = parse_mirrors(input);
+    let mirrors = parse_mirrors(LineReader::open(filename).unwrap());
 
     mirrors.iter().map(|m| m.score_reflection()).sum()
 }
 
+#[derive(Debug)]
 struct Mirror {
     lines: Vec<Vec<char>>,
 }
@@ -44,14 +56,17 @@ impl Mirror {
             if self.row_equals(row, row + 1) {
                 let mut upper = row;
                 let mut lower = row + 1;
-                while upper > 0 && lower < self.lines.len() - 1 {
+                let mut reflects = true;
+                // an equal pair that does not reflect all the way out is not the mirror line,
+                // so keep looking further down
+                while reflects && upper > 0 && lower < self.lines.len() - 1 {
                     upper -= 1;
                     lower += 1;
-                    if !self.row_equals(upper, lower) {
-                        return None;
-                    }
+                    reflects = self.row_equals(upper, lower);
+                }
+                if reflects {
+                    return Some((row, row + 1));
                 }
-                return Some((row, row + 1));
             }
         }
         None
@@ -62,14 +77,15 @@ impl Mirror {
             if self.col_equals(col, col + 1) {
                 let mut left = col;
                 let mut right = col + 1;
-                while left > 0 && right < self.lines[0].len() - 1 {
+                let mut reflects = true;
+                while reflects && left > 0 && right < self.lines[0].len() - 1 {
                     left -= 1;
                     right += 1;
-                    if !self.col_equals(left, right) {
-                        return None;
-                    }
+                    reflects = self.col_equals(left, right);
+                }
+                if reflects {
+                    return Some((col, col + 1));
                 }
-                return Some((col, col + 1));
             }
         }
         None
@@ -92,18 +108,12 @@ impl Mirror {
     }
 }
 
-fn parse_mirrors(input: Vec<String>) -> Vec<Mirror> {
+// the patterns are separated by blank lines
+fn parse_mirrors<R: BufRead>(mut input: LineReader<R>) -> Vec<Mirror> {
     let mut mirrors = Vec::new();
-    let mut mirror = Vec::new();
-    for line in input {
-        if line.is_empty() {
-            mirrors.push(Mirror::new(mirror));
-            mirror = Vec::new();
-        } else {
-            mirror.push(line);
-        }
+    while let Some(section) = input.next_section().unwrap() {
+        mirrors.push(Mirror::new(section.lines().map(String::from).collect()));
     }
-    mirrors.push(Mirror::new(mirror));
 
     mirrors
 }
@@ -111,11 +121,11 @@ fn parse_mirrors(input: Vec<String>) -> Vec<Mirror> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use advent_of_code_2023::read_lines;
+    use proptest::prelude::*;
 
     #[test]
     fn test_parse_mirrors() {
-        let input = read_lines("src/bin/day13/test_input.txt").unwrap();
+        let input = LineReader::open("src/bin/day13/test_input.txt").unwrap();
 
         let mirrors = parse_mirrors(input);
 
@@ -128,7 +138,7 @@ mod tests {
 
     #[test]
     fn test_find_symmetry() {
-        let input = read_lines("src/bin/day13/test_input.txt").unwrap();
+        let input = LineReader::open("src/bin/day13/test_input.txt").unwrap();
         let mirrors = parse_mirrors(input);
 
         let h_symmetry = mirrors[0].find_horizontal_symmetry();
@@ -146,7 +156,7 @@ mod tests {
 
     #[test]
     fn test_score_reflections() {
-        let input = read_lines("src/bin/day13/test_input.txt").unwrap();
+        let input = LineReader::open("src/bin/day13/test_input.txt").unwrap();
         let mirrors = parse_mirrors(input);
 
         let score = mirrors[0].score_reflection();
@@ -156,12 +166,74 @@ mod tests {
         assert_eq!(score, 400);
     }
 
+    #[test]
+    fn test_symmetry_after_partial_reflection() {
+        // rows 1 and 2 are equal but rows 0 and 3 are not, the mirror line is further down
+        let lines = ["#..", ".#.", ".#.", "..#", "##.", "##.", "..#"];
+        let mirror = Mirror {
+            lines: lines.iter().map(|line| line.chars().collect()).collect(),
+        };
+        assert_eq!(mirror.find_horizontal_symmetry(), Some((4, 5)));
+        assert_eq!(transpose(&mirror).find_vertical_symmetry(), Some((4, 5)));
+        assert_eq!(mirror.score_reflection(), 500);
+    }
+
     #[test]
     fn test_part_1() {
-        let input = read_lines("src/bin/day13/input.txt").unwrap();
+        let input = LineReader::open("src/bin/day13/input.txt").unwrap();
         let mirrors = parse_mirrors(input);
         for mirror in &mirrors {
             println!("{:?}", mirror.score_reflection());
         }
     }
+
+    fn transpose(mirror: &Mirror) -> Mirror {
+        let lines = (0..mirror.lines[0].len())
+            .map(|col| mirror.lines.iter().map(|row| row[col]).collect())
+            .collect();
+        Mirror { lines }
+    }
+
+    fn mirror_strategy() -> impl Strategy<Value = Mirror> {
+        (2usize..8, 2usize..8).prop_flat_map(|(rows, cols)| {
+            prop::collection::vec(
+                prop::collection::vec(prop::sample::select(vec!['#', '.']), cols),
+                rows,
+            )
+            .prop_map(|lines| Mirror { lines })
+        })
+    }
+
+    // mirror the first rows of a random pattern so there is a reflection to find
+    fn reflected_strategy() -> impl Strategy<Value = Mirror> {
+        mirror_strategy().prop_map(|mirror| {
+            let mut lines = mirror.lines.clone();
+            lines.extend(mirror.lines.into_iter().rev());
+            Mirror { lines }
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_reflection_symmetric_under_transpose(mirror in mirror_strategy()) {
+            let transposed = transpose(&mirror);
+            prop_assert_eq!(
+                mirror.find_horizontal_symmetry(),
+                transposed.find_vertical_symmetry()
+            );
+            prop_assert_eq!(
+                mirror.find_vertical_symmetry(),
+                transposed.find_horizontal_symmetry()
+            );
+        }
+
+        #[test]
+        fn prop_reflection_is_genuine(mirror in reflected_strategy()) {
+            let (upper, lower) = mirror.find_horizontal_symmetry().unwrap();
+            prop_assert_eq!(upper + 1, lower);
+            for offset in 0..=upper.min(mirror.lines.len() - 1 - lower) {
+                prop_assert!(mirror.row_equals(upper - offset, lower + offset));
+            }
+        }
+    }
 }
@@ -1,4 +1,6 @@
+use advent_of_code_2023::{debug, fuzz, workspace::Puzzle, Simulation};
 use std::collections::HashMap;
+use std::io;
 use std::time::Instant;
 
 // --- Day 14: Parabolic Reflector Dish ---
@@ -6,17 +8,35 @@ const STONE: u8 = b'O';
 const EMPTY: u8 = b'.';
 
 fn main() {
+    if fuzz::from_args("day14", fuzz_target) {
+        return;
+    }
+
+    let input = Puzzle::from_env(2023, 14).input();
+    if std::env::args().any(|arg| arg == "--step") {
+        let input = advent_of_code_2023::read_lines(&input).unwrap();
+        debug(Platform::parse(&input), io::stdin().lock(), io::stdout()).unwrap();
+        return;
+    }
+
     let time_start = Instant::now();
-    let load = part_1("src/bin/day14/input.txt");
+    let load = part_1(&input);
     println!("Part 1: {:?}", load);
     println!("Time: {}μs", time_start.elapsed().as_micros());
 
     let time_start = Instant::now();
-    let load = part_2("src/bin/day14/input.txt");
+    let load = part_2(&input);
     println!("Part 2: {:?}", load);
     println!("Time: {}μs", time_start.elapsed().as_micros());
 }
 
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        part_1(filename);
+        part_2(filename);
+    });
+}
+
 fn part_1(filename: &str) -> u64 {
     let input = advent_of_code_2023::read_lines(filename).unwrap();
     let mut platform = Platform::parse(&input);
@@ -34,12 +54,14 @@ fn part_2(filename: &str) -> u64 {
     platform.calculate_load()
 }
 
+#[derive(Clone)]
 struct Platform {
     rows: Vec<Vec<u8>>,
     cols: Vec<Vec<u8>>,
     previous_states: HashMap<Vec<Vec<u8>>, usize>,
     cycle_count: usize,
     rest_cycle_count: usize,
+    tilt_count: usize,
 }
 
 impl Platform {
@@ -59,6 +81,7 @@ impl Platform {
             previous_states: HashMap::new(),
             cycle_count: 0,
             rest_cycle_count: 0,
+            tilt_count: 0,
         }
     }
 
@@ -178,10 +201,51 @@ impl Platform {
     }
 }
 
+// one step is a single tilt, in the north, west, south, east order of a full cycle
+impl Simulation for Platform {
+    fn step(&mut self) -> bool {
+        match self.tilt_count % 4 {
+            0 => self.tilt_north(),
+            1 => self.tilt_west(),
+            2 => self.tilt_south(),
+            _ => self.tilt_east(),
+        }
+        self.tilt_count += 1;
+        true
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        for row in &self.rows {
+            out.push_str(&String::from_utf8_lossy(row));
+            out.push('\n');
+        }
+        out.push_str(&format!(
+            "tilts: {}, cycles: {}, load: {}\n",
+            self.tilt_count,
+            self.tilt_count / 4,
+            self.calculate_load()
+        ));
+        out
+    }
+
+    // supported conditions: load=<n>, cycle=<n>
+    fn breakpoint(&self, condition: &str) -> bool {
+        match condition.split_once('=') {
+            Some(("load", value)) => value.parse() == Ok(self.calculate_load()),
+            Some(("cycle", value)) => {
+                self.tilt_count.is_multiple_of(4) && value.parse() == Ok(self.tilt_count / 4)
+            }
+            _ => false,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use advent_of_code_2023::read_lines;
+    use advent_of_code_2023::{read_lines, MAX_SNAPSHOTS};
+    use proptest::prelude::*;
 
     #[test]
     fn test_parse() {
@@ -278,4 +342,73 @@ mod tests {
         }
         assert_eq!(platform.calculate_load(), 64);
     }
+
+    #[test]
+    fn test_step_debugger() {
+        let input = read_lines("src/bin/day14/test_input.txt").unwrap();
+        let commands = "break cycle=1\ncontinue\nstep 2\nrewind 2\nprint\n";
+        let mut output = Vec::new();
+        let platform = debug(Platform::parse(&input), commands.as_bytes(), &mut output).unwrap();
+        assert_eq!(platform.tilt_count, 4);
+        assert_eq!(platform.calculate_load(), 87);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("breakpoint cycle=1 at step 4\nstep 6\nstep 4\n"));
+        assert!(output.ends_with("tilts: 4, cycles: 1, load: 87\n"));
+    }
+
+    #[test]
+    fn test_step_debugger_limits() {
+        // the platform tilts forever, so continue stops after the steps it is given and rewind
+        // only goes back as far as the snapshots that are kept
+        let input = read_lines("src/bin/day14/test_input.txt").unwrap();
+        let commands = format!(
+            "continue 10\ncontinue {}\nrewind {}\n",
+            MAX_SNAPSHOTS,
+            2 * MAX_SNAPSHOTS
+        );
+        let mut output = Vec::new();
+        let platform = debug(Platform::parse(&input), commands.as_bytes(), &mut output).unwrap();
+        assert_eq!(platform.tilt_count, 10);
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(
+            output,
+            format!(
+                "stopped at step 10\nstopped at step {}\nstep 10\n",
+                MAX_SNAPSHOTS + 10
+            )
+        );
+    }
+
+    fn platform_strategy() -> impl Strategy<Value = Vec<String>> {
+        (1usize..10, 1usize..10).prop_flat_map(|(rows, cols)| {
+            prop::collection::vec(
+                prop::collection::vec(prop::sample::select(vec!['O', '#', '.']), cols)
+                    .prop_map(|row| row.into_iter().collect::<String>()),
+                rows,
+            )
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_tilts_are_idempotent(input in platform_strategy()) {
+            let tilts: [fn(&mut Platform); 4] = [
+                Platform::tilt_north,
+                Platform::tilt_west,
+                Platform::tilt_south,
+                Platform::tilt_east,
+            ];
+            for tilt in tilts {
+                let mut platform = Platform::parse(&input);
+                tilt(&mut platform);
+                let once = platform.rows.clone();
+                tilt(&mut platform);
+                prop_assert_eq!(&platform.rows, &once);
+                // rows and columns stay two views of the same grid
+                let cols = platform.cols.clone();
+                platform.recalculate_cols();
+                prop_assert_eq!(&platform.cols, &cols);
+            }
+        }
+    }
 }
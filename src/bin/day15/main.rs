@@ -1,28 +1,41 @@
 // --- Day 15: Lens Library ---
 
-use advent_of_code_2023::read_lines;
+use advent_of_code_2023::{fuzz, workspace::Puzzle, LineReader};
 use std::collections::HashMap;
+use std::io::BufRead;
 use std::time::Instant;
 
 fn main() {
+    if fuzz::from_args("day15", fuzz_target) {
+        return;
+    }
+
+    let input = Puzzle::from_env(2023, 15).input();
     let time_start = Instant::now();
-    let sum = part_1("src/bin/day15/input.txt");
+    let sum = part_1(&input);
     println!("Part 1: {:?}", sum);
     println!("Time: {}μs", time_start.elapsed().as_micros());
 
     let time_start = Instant::now();
-    let sum = unsafe { part_2("src/bin/day15/input.txt") };
+    let sum = part_2(&input);
     println!("Part 2: {:?}", sum);
     println!("Time: {}μs", time_start.elapsed().as_micros());
 }
 
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        part_1(filename);
+        part_2(filename);
+    });
+}
+
 fn part_1(filename: &str) -> u64 {
-    let input = read_lines(filename).unwrap();
+    let input = LineReader::open(filename).unwrap();
     calculate_sum_of_hashes(input)
 }
 
-unsafe fn part_2(filename: &str) -> u64 {
-    let input = read_lines(filename).unwrap();
+fn part_2(filename: &str) -> u64 {
+    let input = LineReader::open(filename).unwrap();
     let hashmap = create_boxes(input);
     calculate_focusing_powers(hashmap)
 }
@@ -37,46 +50,57 @@ fn label_hash(input: &[u8]) -> u32 {
     sum
 }
 
-fn calculate_sum_of_hashes(input: Vec<String>) -> u64 {
-    input
-        .iter()
-        .flat_map(|line| {
-            line.split(',')
-                .map(|seq| label_hash(&seq.as_bytes()) as u64)
-        })
-        .sum()
+// the whole sequence is on one line, so the input is streamed step by step between commas
+fn calculate_sum_of_hashes<R: BufRead>(mut input: LineReader<R>) -> u64 {
+    let mut sum = 0;
+    while let Some(seq) = input.next_record(b',').unwrap() {
+        sum += label_hash(seq.trim().as_bytes()) as u64;
+    }
+    sum
 }
 
-unsafe fn create_boxes(input: Vec<String>) -> Vec<HashMap<String, (String, usize)>> {
+fn create_boxes<R: BufRead>(mut input: LineReader<R>) -> Vec<HashMap<String, (String, usize)>> {
     let mut boxes: Vec<HashMap<String, (String, usize)>> = vec![HashMap::new(); 256];
-    for line in &input {
-        for seq in line.split(',') {
-            let b = seq.as_bytes();
-            if let Some(_value) = b[b.len() - 1].checked_sub(b'0') {
-                let label = &b[0..b.len() - 2];
-                let label_hash = label_hash(label) as usize;
-                let len = boxes[label_hash].len() + 1;
-                boxes[label_hash]
-                    .entry(String::from_utf8_unchecked(label.to_vec()))
-                    .and_modify(|v| *v = (seq.to_string(), v.1))
-                    .or_insert((seq.to_string(), len));
-            } else {
-                let label = &b[0..b.len() - 1];
-                let str_label = &String::from_utf8_unchecked(label.to_vec());
-                let label_hash = label_hash(label) as usize;
-                if boxes[label_hash].contains_key(str_label) {
-                    let slot = boxes[label_hash][str_label].1;
-                    boxes[label_hash].remove(str_label);
-                    for (_key, value) in boxes[label_hash].iter_mut() {
-                        if value.1 > slot {
-                            value.1 -= 1;
-                        }
-                    }
+    while let Some(seq) = input.next_record(b',').unwrap() {
+        let seq = seq.trim();
+        if seq.is_empty() {
+            continue;
+        }
+        // a malformed step is skipped rather than guessed at
+        if let Err(error) = apply_step(&mut boxes, seq) {
+            eprintln!("{}, skipped", error);
+        }
+    }
+    boxes
+}
+
+// a step is either `label=N` with a focal length from 1 to 9, or `label-`
+fn apply_step(boxes: &mut [HashMap<String, (String, usize)>], seq: &str) -> Result<(), String> {
+    if let Some((label, value)) = seq.split_once('=') {
+        if label.is_empty() || !matches!(value.as_bytes(), [b'1'..=b'9']) {
+            return Err(format!("Invalid step: {}", seq));
+        }
+        let label_hash = label_hash(label.as_bytes()) as usize;
+        let len = boxes[label_hash].len() + 1;
+        boxes[label_hash]
+            .entry(label.to_string())
+            .and_modify(|v| *v = (seq.to_string(), v.1))
+            .or_insert((seq.to_string(), len));
+    } else if let Some(str_label) = seq.strip_suffix('-').filter(|label| !label.is_empty()) {
+        let label_hash = label_hash(str_label.as_bytes()) as usize;
+        if boxes[label_hash].contains_key(str_label) {
+            let slot = boxes[label_hash][str_label].1;
+            boxes[label_hash].remove(str_label);
+            for (_key, value) in boxes[label_hash].iter_mut() {
+                if value.1 > slot {
+                    value.1 -= 1;
                 }
             }
         }
+    } else {
+        return Err(format!("Invalid step: {}", seq));
     }
-    boxes
+    Ok(())
 }
 
 fn calculate_focusing_powers(boxes: Vec<HashMap<String, (String, usize)>>) -> u64 {
@@ -99,6 +123,7 @@ fn calculate_focusing_powers(boxes: Vec<HashMap<String, (String, usize)>>) -> u6
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code_2023::fuzz::Outcome;
 
     #[test]
     fn test_calculate_step() {
@@ -118,7 +143,8 @@ mod tests {
 
     #[test]
     fn test_sum_steps() {
-        let input = vec!["rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7".to_string()];
+        let input =
+            LineReader::new("rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7".as_bytes());
         assert_eq!(calculate_sum_of_hashes(input), 1320);
     }
 
@@ -129,8 +155,9 @@ mod tests {
 
     #[test]
     fn test_create_boxes() {
-        let input = vec!["rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7".to_string()];
-        let boxes = unsafe { create_boxes(input) };
+        let input =
+            LineReader::new("rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7".as_bytes());
+        let boxes = create_boxes(input);
         assert_eq!(boxes[0]["rn"], ("rn=1".to_string(), 1));
         assert_eq!(boxes[0]["cm"], ("cm=2".to_string(), 2));
         assert_eq!(boxes[3]["ot"], ("ot=7".to_string(), 1));
@@ -145,15 +172,62 @@ mod tests {
 
     #[test]
     fn test_calculate_focusing_powers() {
-        let input = vec!["rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7".to_string()];
-        let hashmap = unsafe { create_boxes(input) };
+        let input =
+            LineReader::new("rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7".as_bytes());
+        let hashmap = create_boxes(input);
         assert_eq!(calculate_focusing_powers(hashmap), 145);
     }
 
     #[test]
     fn test_part_2() {
-        unsafe {
-            assert_eq!(part_2("src/bin/day15/test_input.txt"), 145);
+        assert_eq!(part_2("src/bin/day15/test_input.txt"), 145);
+    }
+
+    #[test]
+    fn test_fuzz_non_ascii_label() {
+        // malformed steps, like a multi-byte label cut in half, are skipped rather than crash
+        let timeout = std::time::Duration::from_secs(5);
+        for input in ["ab=1,é-", "é1", "é=1,é-", ",,=1,-,a=,a=10,a=x,"] {
+            assert_eq!(
+                fuzz::run_one(fuzz_target, input.as_bytes(), timeout),
+                Outcome::Pass
+            );
+        }
+    }
+
+    #[test]
+    fn test_fuzz_crashes_in_parallel() {
+        // every run reports its own panic, however many run at once
+        let timeout = std::time::Duration::from_secs(5);
+        let runs = (0..8)
+            .map(|_| {
+                std::thread::spawn(move || {
+                    fuzz::run_one(|data| assert!(data.is_empty()), b"x", timeout)
+                })
+            })
+            .collect::<Vec<_>>();
+        for run in runs {
+            match run.join().unwrap() {
+                Outcome::Crash(location) => assert!(location.contains("day15"), "{}", location),
+                outcome => panic!("expected a crash, got {:?}", outcome),
+            }
+        }
+    }
+
+    #[test]
+    fn test_apply_step() {
+        let mut boxes = vec![HashMap::new(); 256];
+        for step in ["é1", "=1", "a=", "a=0", "a=10", "a=x", "-", "a"] {
+            assert_eq!(
+                apply_step(&mut boxes, step),
+                Err(format!("Invalid step: {}", step))
+            );
         }
+        assert!(boxes.iter().all(|b| b.is_empty()));
+        assert_eq!(apply_step(&mut boxes, "é=3"), Ok(()));
+        let label_hash = label_hash("é".as_bytes()) as usize;
+        assert_eq!(boxes[label_hash]["é"], ("é=3".to_string(), 1));
+        assert_eq!(apply_step(&mut boxes, "é-"), Ok(()));
+        assert!(boxes[label_hash].is_empty());
     }
 }
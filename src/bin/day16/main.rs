@@ -1,4 +1,5 @@
-use advent_of_code_2023::read_lines;
+use advent_of_code_2023::{debug, fuzz, read_lines, workspace::Puzzle, Simulation};
+use std::io;
 use std::time::Instant;
 
 #[derive(Clone)]
@@ -7,6 +8,7 @@ struct Tile {
     state: [u8; 4], // [0] left, [1] up, [2] right, [3] down; 1 = visited, 0 = not visited
 }
 
+#[derive(Clone)]
 struct Contraption {
     tiles: Vec<Vec<Tile>>,
     width: usize,
@@ -160,18 +162,149 @@ impl Contraption {
     }
 }
 
+// beam directions, matching the tile state indices set when a beam enters a tile
+const RIGHT: usize = 0;
+const DOWN: usize = 1;
+const LEFT: usize = 2;
+const UP: usize = 3;
+
+// Steps the beam from the top left corner one tile at a time for the debugger,
+// every beam head moves once per step.
+#[derive(Clone)]
+struct BeamTrace {
+    contraption: Contraption,
+    beams: Vec<(usize, usize, usize)>,
+    ticks: usize,
+}
+
+impl BeamTrace {
+    fn new(contraption: Contraption) -> Self {
+        Self {
+            contraption,
+            beams: vec![(0, 0, RIGHT)],
+            ticks: 0,
+        }
+    }
+
+    fn turn(value: u8, direction: usize) -> Vec<usize> {
+        match (value, direction) {
+            (b'|', RIGHT | LEFT) => vec![DOWN, UP],
+            (b'-', UP | DOWN) => vec![RIGHT, LEFT],
+            (b'/', RIGHT) | (b'\\', LEFT) => vec![UP],
+            (b'/', LEFT) | (b'\\', RIGHT) => vec![DOWN],
+            (b'/', UP) | (b'\\', DOWN) => vec![RIGHT],
+            (b'/', DOWN) | (b'\\', UP) => vec![LEFT],
+            _ => vec![direction],
+        }
+    }
+
+    fn advance(&self, x: usize, y: usize, direction: usize) -> Option<(usize, usize)> {
+        match direction {
+            RIGHT if y + 1 < self.contraption.width => Some((x, y + 1)),
+            DOWN if x + 1 < self.contraption.height => Some((x + 1, y)),
+            LEFT if y > 0 => Some((x, y - 1)),
+            UP if x > 0 => Some((x - 1, y)),
+            _ => None,
+        }
+    }
+}
+
+impl Simulation for BeamTrace {
+    fn step(&mut self) -> bool {
+        let mut next = Vec::new();
+        for (x, y, direction) in std::mem::take(&mut self.beams) {
+            if self.contraption.tiles[x][y].state[direction] == 1 {
+                continue;
+            }
+            self.contraption.tiles[x][y].state[direction] = 1;
+            for turned in Self::turn(self.contraption.next(x, y), direction) {
+                if let Some((nx, ny)) = self.advance(x, y, turned) {
+                    if !next.contains(&(nx, ny, turned)) {
+                        next.push((nx, ny, turned));
+                    }
+                }
+            }
+        }
+        self.beams = next;
+        self.ticks += 1;
+        !self.beams.is_empty()
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        for x in 0..self.contraption.height {
+            for y in 0..self.contraption.width {
+                let head = self.beams.iter().find(|b| b.0 == x && b.1 == y);
+                out.push(match head {
+                    Some((_, _, RIGHT)) => '>',
+                    Some((_, _, DOWN)) => 'v',
+                    Some((_, _, LEFT)) => '<',
+                    Some(_) => '^',
+                    None if self.contraption.tiles[x][y].state.contains(&1) => '#',
+                    None => self.contraption.next(x, y) as char,
+                });
+            }
+            out.push('\n');
+        }
+        out.push_str(&format!(
+            "ticks: {}, beams: {}, energized: {}\n",
+            self.ticks,
+            self.beams.len(),
+            self.contraption.num_of_energized()
+        ));
+        out
+    }
+
+    // supported conditions: energized=<n>, beams=<n>, tile=<row>,<col> (tile is energized)
+    fn breakpoint(&self, condition: &str) -> bool {
+        match condition.split_once('=') {
+            Some(("energized", value)) => value.parse() == Ok(self.contraption.num_of_energized()),
+            Some(("beams", value)) => value.parse() == Ok(self.beams.len()),
+            Some(("tile", value)) => match value.split_once(',') {
+                Some((x, y)) => match (x.parse::<usize>(), y.parse::<usize>()) {
+                    (Ok(x), Ok(y)) if x < self.contraption.height && y < self.contraption.width => {
+                        self.contraption.tiles[x][y].state.contains(&1)
+                    }
+                    _ => false,
+                },
+                None => false,
+            },
+            _ => false,
+        }
+    }
+}
+
 fn main() {
+    if fuzz::from_args("day16", fuzz_target) {
+        return;
+    }
+
+    let input = Puzzle::from_env(2023, 16).input();
+    if std::env::args().any(|arg| arg == "--step") {
+        let input = read_lines(&input).unwrap();
+        let trace = BeamTrace::new(Contraption::new(input));
+        debug(trace, io::stdin().lock(), io::stdout()).unwrap();
+        return;
+    }
+
     let time_start = Instant::now();
-    let sum = part_1("src/bin/day16/input.txt");
+    let sum = part_1(&input);
     println!("Part 1: {:?}", sum);
     println!("Time: {}μs", time_start.elapsed().as_micros());
 
     let time_start = Instant::now();
-    let sum = part_2("src/bin/day16/input.txt");
+    let sum = part_2(&input);
     println!("Part 2: {:?}", sum);
     println!("Time: {}μs", time_start.elapsed().as_micros());
 }
 
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        part_1(filename);
+        part_2(filename);
+    });
+}
+
 fn part_1(filename: &str) -> u64 {
     let input = read_lines(filename).unwrap();
     let mut contraption = Contraption::new(input);
@@ -226,4 +359,17 @@ mod tests {
     fn test_part_two() {
         assert_eq!(part_2("src/bin/day16/test_input.txt"), 51);
     }
+
+    #[test]
+    fn test_beam_trace() {
+        let input = read_lines("src/bin/day16/test_input.txt").unwrap();
+        let commands = "break tile=9,1\ncontinue\nclear\ncontinue\n";
+        let mut output = Vec::new();
+        let trace = BeamTrace::new(Contraption::new(input));
+        let trace = debug(trace, commands.as_bytes(), &mut output).unwrap();
+        assert!(trace.beams.is_empty());
+        assert_eq!(trace.contraption.num_of_energized(), 46);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("breakpoint tile=9,1 at step"));
+    }
 }
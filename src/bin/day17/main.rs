@@ -1,12 +1,17 @@
 // --- Day 17: Clumsy Crucible ---
 
-use advent_of_code_2023::read_lines;
+use advent_of_code_2023::{fuzz, read_lines, workspace::Puzzle};
 use std::cmp::Ordering;
 use std::collections::{BinaryHeap, HashSet};
 
 fn main() {
+    if fuzz::from_args("day17", fuzz_target) {
+        return;
+    }
+
+    let input = Puzzle::from_env(2023, 17).input();
     let time_start = std::time::Instant::now();
-    let sum = part_1("src/bin/day17/input.txt");
+    let sum = part_1(&input);
     println!(
         "Part 1: {:?}, Time: {}μs",
         sum,
@@ -14,7 +19,7 @@ fn main() {
     );
 
     let time_start = std::time::Instant::now();
-    let sum = part_2("src/bin/day17/input.txt");
+    let sum = part_2(&input);
     println!(
         "Part 2: {:?}, Time: {}μs",
         sum,
@@ -22,6 +27,13 @@ fn main() {
     );
 }
 
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        part_1(filename);
+        part_2(filename);
+    });
+}
+
 fn part_1(filename: &str) -> i32 {
     let input = read_lines(filename).unwrap();
     let mut graph = Graph::new(input);
@@ -1,11 +1,16 @@
 // --- Day 18: Lavaduct Lagoon ---
 
-use advent_of_code_2023::read_lines;
+use advent_of_code_2023::{fuzz, read_lines, workspace::Puzzle};
 use itertools::Itertools;
 
 fn main() {
+    if fuzz::from_args("day18", fuzz_target) {
+        return;
+    }
+
+    let input = Puzzle::from_env(2023, 18).input();
     let time_start = std::time::Instant::now();
-    let area = part_1("src/bin/day18/input.txt");
+    let area = part_1(&input);
     println!(
         "Part 1: {:?} , Time: {}μs",
         area,
@@ -13,7 +18,7 @@ fn main() {
     );
 
     let time_start = std::time::Instant::now();
-    let area = part_2("src/bin/day18/input.txt");
+    let area = part_2(&input);
     println!(
         "Part 2: {:?} , Time: {}μs",
         area,
@@ -21,21 +26,29 @@ fn main() {
     );
 }
 
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        part_1(filename);
+        part_2(filename);
+    });
+}
+
 fn part_1(filename: &str) -> i64 {
     let instructions = read_lines(filename).unwrap();
-    let points = collect_points_part_1(instructions);
-    let area = get_area(points.0);
-    // the points in the grid are in the middle of the squares, so we cannot use the area formula directly,
-    // because we would be losing 1/2 of the area of the squares
-    // we use Pick's theorem instead: A = I + B/2 - 1
-    // where A is the area of the grid, I is the number of points inside the grid, and B is the number of points on the boundary
-    area - points.1 / 2 + 1 + points.1
+    lagoon_size(collect_points_part_1(instructions))
 }
 
 fn part_2(filename: &str) -> i64 {
     let instructions = read_lines(filename).unwrap();
-    let points = collect_points_part_2(instructions);
+    lagoon_size(collect_points_part_2(instructions))
+}
+
+fn lagoon_size(points: (Vec<(i64, i64)>, i64)) -> i64 {
     let area = get_area(points.0);
+    // the points in the grid are in the middle of the squares, so we cannot use the area formula directly,
+    // because we would be losing 1/2 of the area of the squares
+    // we use Pick's theorem instead: A = I + B/2 - 1
+    // where A is the area of the grid, I is the number of points inside the grid, and B is the number of points on the boundary
     area - points.1 / 2 + 1 + points.1
 }
 
@@ -109,6 +122,7 @@ fn get_area(points: Vec<(i64, i64)>) -> i64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_collect_points() {
@@ -172,4 +186,30 @@ mod tests {
     fn test_part_2() {
         assert_eq!(part_2("src/bin/day18/test_input.txt"), 952408144115);
     }
+
+    proptest! {
+        #[test]
+        fn prop_area_is_translation_invariant(
+            points in prop::collection::vec((-50i64..50, -50i64..50), 3..12),
+            dx in -1000i64..1000,
+            dy in -1000i64..1000,
+        ) {
+            let translated = points.iter().map(|(x, y)| (x + dx, y + dy)).collect();
+            prop_assert_eq!(get_area(translated), get_area(points));
+        }
+
+        #[test]
+        fn prop_rectangle_lagoon(width in 1i64..100, height in 1i64..100) {
+            let instructions = vec![
+                format!("R {} (#000000)", width),
+                format!("D {} (#000000)", height),
+                format!("L {} (#000000)", width),
+                format!("U {} (#000000)", height),
+            ];
+            let (points, boundary) = collect_points_part_1(instructions);
+            prop_assert_eq!(lagoon_size((points.clone(), boundary)), (width + 1) * (height + 1));
+            let shifted = points.iter().map(|(x, y)| (x - height, y + width)).collect();
+            prop_assert_eq!(lagoon_size((shifted, boundary)), (width + 1) * (height + 1));
+        }
+    }
 }
@@ -1,6 +1,6 @@
 // --- Day 19: Aplenty ---
 
-use advent_of_code_2023::read_lines;
+use advent_of_code_2023::{fuzz, read_lines, workspace::Puzzle};
 use std::collections::{HashMap, VecDeque};
 
 #[derive(Debug, PartialEq)]
@@ -291,8 +291,13 @@ fn count(
 }
 
 fn main() {
+    if fuzz::from_args("day19", fuzz_target) {
+        return;
+    }
+
+    let input = Puzzle::from_env(2023, 19).input();
     let time_start = std::time::Instant::now();
-    let ratings = part_1("src/bin/day19/input.txt");
+    let ratings = part_1(&input);
     println!(
         "Part 1: {:?} , Time: {}μs",
         ratings,
@@ -300,7 +305,7 @@ fn main() {
     );
 
     let time_start = std::time::Instant::now();
-    let count = part_2(read_lines("src/bin/day19/input.txt").unwrap());
+    let count = part_2(read_lines(&input).unwrap());
     println!(
         "Part 2: {:?} , Time: {}μs",
         count,
@@ -308,6 +313,13 @@ fn main() {
     );
 }
 
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        part_1(filename);
+        part_2(read_lines(filename).unwrap());
+    });
+}
+
 fn part_1(filename: &str) -> i64 {
     let input_lines = read_lines(filename).unwrap();
     process_parts_1(input_lines)
@@ -1,12 +1,24 @@
 // --- Day 20: Pulse Propagation ---
 
-use advent_of_code_2023::{lcm, read_lines};
+use advent_of_code_2023::{debug, fuzz, lcm, read_lines, workspace::Puzzle, Simulation};
 use itertools::Itertools;
 use std::collections::{HashMap, VecDeque};
+use std::io;
 
 fn main() {
+    if fuzz::from_args("day20", fuzz_target) {
+        return;
+    }
+
+    let input = Puzzle::from_env(2023, 20).input();
+    if std::env::args().any(|arg| arg == "--step") {
+        let input = read_lines(&input).unwrap();
+        debug(Circuit::new(input), io::stdin().lock(), io::stdout()).unwrap();
+        return;
+    }
+
     let time_start = std::time::Instant::now();
-    let count = part_1("src/bin/day20/input.txt", 1000);
+    let count = part_1(&input, 1000);
     println!(
         "Part 1: {:?}  Time: {}μs",
         count,
@@ -14,7 +26,7 @@ fn main() {
     );
 
     let time_start = std::time::Instant::now();
-    let count = part_2("src/bin/day20/input.txt");
+    let count = part_2(&input);
     println!(
         "Part 2: {:?}  Time: {}μs",
         count,
@@ -22,6 +34,13 @@ fn main() {
     );
 }
 
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        part_1(filename, 1000);
+        part_2(filename);
+    });
+}
+
 fn part_1(filename: &str, num: usize) -> usize {
     let input = read_lines(filename).unwrap();
     let mut circuit = Circuit::new(input);
@@ -51,9 +70,12 @@ fn part_2(filename: &str) -> i64 {
 const BROADCASTER: &str = "broadcaster";
 const OUTPUT: &str = "output";
 
+#[derive(Clone)]
 struct Circuit {
     modules: HashMap<String, Module>,
     count: usize,
+    pending: VecDeque<Propagation>,
+    last: Option<Propagation>,
     rx_module: Option<String>,
     rx_senders: HashMap<String, usize>,
     cycle_lengths: HashMap<String, usize>,
@@ -66,6 +88,8 @@ impl Circuit {
         Circuit {
             modules,
             count: 0,
+            pending: VecDeque::new(),
+            last: None,
             rx_module: None,
             rx_senders: HashMap::new(),
             cycle_lengths: HashMap::new(),
@@ -83,22 +107,33 @@ impl Circuit {
         None
     }
     fn broadcast(&mut self) {
+        self.press_button();
+        while self.deliver_next() {}
+    }
+
+    fn press_button(&mut self) {
         self.count += 1;
-        let mut pulses = VecDeque::new();
+        self.last = None;
         let broadcaster = self.modules.get_mut(BROADCASTER).unwrap();
         for propagation in broadcaster.propagate(Pulse::Low) {
-            pulses.push_back(propagation);
+            self.pending.push_back(propagation);
         }
+    }
 
-        while let Some(propagation) = pulses.pop_front() {
-            if let Some(module) = self.modules.get_mut(&propagation.2) {
-                if let Some(new_pulse) = module.pulse(&propagation.0, propagation.1) {
-                    for next in module.propagate(new_pulse) {
-                        pulses.push_back(next);
-                    }
+    // deliver the oldest pending pulse, returns false if there was none
+    fn deliver_next(&mut self) -> bool {
+        let Some(propagation) = self.pending.pop_front() else {
+            return false;
+        };
+        if let Some(module) = self.modules.get_mut(&propagation.2) {
+            if let Some(new_pulse) = module.pulse(&propagation.0, propagation.1) {
+                for next in module.propagate(new_pulse) {
+                    self.pending.push_back(next);
                 }
             }
         }
+        self.last = Some(propagation);
+        true
     }
 
     fn broadcast_2(&mut self) {
@@ -149,9 +184,62 @@ impl Circuit {
     }
 }
 
+#[derive(Clone)]
 struct Propagation(String, Pulse, String);
 
-#[derive(Debug, PartialEq)]
+// one step either presses the button or delivers a single pulse
+impl Simulation for Circuit {
+    fn step(&mut self) -> bool {
+        if self.pending.is_empty() {
+            self.press_button();
+        } else {
+            self.deliver_next();
+        }
+        true
+    }
+
+    fn render(&self) -> String {
+        let mut out = format!("presses: {}, pending: {}\n", self.count, self.pending.len());
+        match &self.last {
+            Some(Propagation(from, pulse, to)) => {
+                out.push_str(&format!("last: {} -{:?}-> {}\n", from, pulse, to))
+            }
+            None => out.push_str("last: button -Low-> broadcaster\n"),
+        }
+        for name in self.modules.keys().sorted() {
+            let module = &self.modules[name];
+            match module.module_type {
+                ModuleType::Switch => {
+                    let on = module.state == ModuleState::On;
+                    out.push_str(&format!("%{}: {}\n", name, if on { "on" } else { "off" }));
+                }
+                ModuleType::Conjunction => {
+                    let memory = module
+                        .senders
+                        .iter()
+                        .sorted_by_key(|(sender, _)| *sender)
+                        .map(|(sender, pulse)| format!("{}={:?}", sender, pulse))
+                        .join(", ");
+                    out.push_str(&format!("&{}: {}\n", name, memory));
+                }
+                _ => (),
+            }
+        }
+        out
+    }
+
+    // supported conditions: presses=<n>, <module>=high, <module>=low (module receives the pulse)
+    fn breakpoint(&self, condition: &str) -> bool {
+        match (condition.split_once('='), &self.last) {
+            (Some(("presses", value)), _) => value.parse() == Ok(self.count),
+            (Some((module, "high")), Some(last)) => last.2 == module && last.1 == Pulse::High,
+            (Some((module, "low")), Some(last)) => last.2 == module && last.1 == Pulse::Low,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 enum ModuleType {
     Broadcaster,
     Conjunction,
@@ -159,7 +247,7 @@ enum ModuleType {
     Switch,
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, PartialEq)]
 enum ModuleState {
     High,
     Low,
@@ -173,6 +261,7 @@ enum Pulse {
     Low,
 }
 
+#[derive(Clone)]
 struct Module {
     high_count: usize,
     low_count: usize,
@@ -379,6 +468,26 @@ mod tests {
         assert_eq!(low_count, 4);
     }
 
+    #[test]
+    fn test_step_debugger() {
+        let input = vec![
+            "broadcaster -> a".to_string(),
+            "%a -> inv, con".to_string(),
+            "&inv -> b".to_string(),
+            "%b -> con".to_string(),
+            "&con -> output".to_string(),
+        ];
+        let commands = "break b=low\ncontinue\nprint\nrewind 3\n";
+        let mut output = Vec::new();
+        let circuit = debug(Circuit::new(input), commands.as_bytes(), &mut output).unwrap();
+        assert_eq!(circuit.count, 1);
+        assert_eq!(circuit.pending.len(), 2);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("breakpoint b=low at step 5\npresses: 1, pending: 1\n"));
+        assert!(output.contains("last: inv -Low-> b\n%a: on\n%b: on\n"));
+        assert!(output.ends_with("step 2\n"));
+    }
+
     #[test]
     fn test_part_1() {
         assert_eq!(part_1("src/bin/day20/test_input.txt", 1000), 32000000);
@@ -1,9 +1,21 @@
-use advent_of_code_2023::read_lines;
+use advent_of_code_2023::{debug, fuzz, read_lines, workspace::Puzzle, Simulation};
 use std::collections::{HashSet, VecDeque};
+use std::io;
 
 fn main() {
+    if fuzz::from_args("day21", fuzz_target) {
+        return;
+    }
+
+    let input = Puzzle::from_env(2023, 21).input();
+    if std::env::args().any(|arg| arg == "--step") {
+        let lines = read_lines(&input).unwrap();
+        debug(Garden::new(lines), io::stdin().lock(), io::stdout()).unwrap();
+        return;
+    }
+
     let time_start = std::time::Instant::now();
-    let count = part_1("src/bin/day21/input.txt", 64);
+    let count = part_1(&input, 64);
     println!(
         "Part 1: {:?}  Time: {}μs",
         count,
@@ -11,7 +23,7 @@ fn main() {
     );
 
     let time_start = std::time::Instant::now();
-    let count = part_2();
+    let count = part_2(&input);
     println!(
         "Part 2: {:?}  Time: {}μs",
         count,
@@ -19,6 +31,16 @@ fn main() {
     );
 }
 
+// part_1 prints the garden and part_2 only solves the real input, so step the garden directly
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        let mut garden = Garden::new(read_lines(filename).unwrap());
+        for _ in 0..64 {
+            garden.make_step();
+        }
+    });
+}
+
 fn part_1(filename: &str, num: usize) -> usize {
     let lines = read_lines(filename).unwrap();
     let mut garden = Garden::new(lines);
@@ -29,10 +51,10 @@ fn part_1(filename: &str, num: usize) -> usize {
     garden.tiles.len()
 }
 
-fn part_2() -> usize {
+fn part_2(filename: &str) -> usize {
     // watch the explanation here: https://youtu.be/9UOMZSL0JTg?si=fRbkrVPlbZm1_TJ8
     let steps = 26501365;
-    let lines = read_lines("src/bin/day21/input.txt").unwrap();
+    let lines = read_lines(filename).unwrap();
     let garden = Garden::new(lines);
     // assuming start tile is in the middle
     assert_eq!(garden.start.0, garden.size / 2);
@@ -81,11 +103,13 @@ const ROCK: char = '#';
 const START: char = 'S';
 const TILE: char = 'O';
 
+#[derive(Clone)]
 struct Garden {
     garden: Vec<Vec<char>>,
     start: (usize, usize),
     size: usize,
     tiles: HashSet<(usize, usize)>,
+    steps: usize,
 }
 
 impl Garden {
@@ -109,6 +133,7 @@ impl Garden {
             start,
             size,
             tiles: HashSet::new(),
+            steps: 0,
         }
     }
 
@@ -136,6 +161,7 @@ impl Garden {
             self.tiles = new_tiles;
         }
         self.garden = new_garden;
+        self.steps += 1;
     }
 
     fn update_tiles(&mut self, i: usize, j: usize) -> Vec<(usize, usize)> {
@@ -154,10 +180,7 @@ impl Garden {
 
     fn print_garden(&self) {
         for row in &self.garden {
-            for c in row {
-                print!("{}", c);
-            }
-            println!();
+            println!("{}", row.iter().collect::<String>());
         }
     }
 
@@ -200,6 +223,43 @@ impl Garden {
     }
 }
 
+impl Simulation for Garden {
+    fn step(&mut self) -> bool {
+        self.make_step();
+        true
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        for row in &self.garden {
+            out.push_str(&row.iter().collect::<String>());
+            out.push('\n');
+        }
+        out.push_str(&format!(
+            "steps: {}, tiles: {}\n",
+            self.steps,
+            self.tiles.len()
+        ));
+        out
+    }
+
+    // supported conditions: steps=<n>, tiles=<n>, tile=<row>,<col> (tile is reached)
+    fn breakpoint(&self, condition: &str) -> bool {
+        match condition.split_once('=') {
+            Some(("steps", value)) => value.parse() == Ok(self.steps),
+            Some(("tiles", value)) => value.parse() == Ok(self.tiles.len()),
+            Some(("tile", value)) => match value.split_once(',') {
+                Some((i, j)) => match (i.parse(), j.parse()) {
+                    (Ok(i), Ok(j)) => self.tiles.contains(&(i, j)),
+                    _ => false,
+                },
+                None => false,
+            },
+            _ => false,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -250,4 +310,23 @@ mod tests {
     fn test_part_1() {
         assert_eq!(part_1("src/bin/day21/test_input.txt", 6), 16);
     }
+
+    #[test]
+    fn test_step_debugger() {
+        let lines = vec![
+            String::from("S.#"),
+            String::from(".#."),
+            String::from("..."),
+        ];
+        let commands = "break tile=2,2\ncontinue\nrewind\nprint\n";
+        let mut output = Vec::new();
+        let garden = debug(Garden::new(lines), commands.as_bytes(), &mut output).unwrap();
+        assert_eq!(garden.steps, 3);
+        assert_eq!(garden.tiles.len(), 3);
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(
+            output,
+            "breakpoint tile=2,2 at step 4\nstep 3\n.O#\nO#.\n.O.\nsteps: 3, tiles: 3\n"
+        );
+    }
 }
@@ -1,11 +1,25 @@
 // --- Day 22: Sand Slabs ---
 
-use advent_of_code_2023::read_lines;
+use advent_of_code_2023::{debug, fuzz, read_lines, workspace::Puzzle, Simulation};
 use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::io;
 
 fn main() {
+    if fuzz::from_args("day22", fuzz_target) {
+        return;
+    }
+
+    let input = Puzzle::from_env(2023, 22).input();
+    if std::env::args().any(|arg| arg == "--step") {
+        let input = read_lines(&input).unwrap();
+        let mut stack = Stack::new();
+        stack.load_bricks(input);
+        debug(stack, io::stdin().lock(), io::stdout()).unwrap();
+        return;
+    }
+
     let time_start = std::time::Instant::now();
-    let count = part_1("src/bin/day22/input.txt");
+    let count = part_1(&input);
     println!(
         "Part 1: {:?}  Time: {}μs",
         count,
@@ -13,7 +27,7 @@ fn main() {
     );
 
     let time_start = std::time::Instant::now();
-    let sum = part_2("src/bin/day22/input.txt");
+    let sum = part_2(&input);
     println!(
         "Part 2: {:?}  Time: {}μs",
         sum,
@@ -21,6 +35,13 @@ fn main() {
     );
 }
 
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        part_1(filename);
+        part_2(filename);
+    });
+}
+
 fn part_1(filename: &str) -> usize {
     let input = read_lines(filename).unwrap();
     let input_len = input.len();
@@ -100,8 +121,10 @@ fn make_bricks(input: Vec<String>) -> Vec<Brick> {
     bricks
 }
 
+#[derive(Clone)]
 struct Stack {
     bricks: BTreeMap<usize, Brick>,
+    pending: VecDeque<Brick>,
     levels: HashMap<usize, Vec<Vec<usize>>>,
     max_x: usize,
     max_y: usize,
@@ -111,6 +134,7 @@ impl Stack {
     fn new() -> Stack {
         Stack {
             bricks: BTreeMap::new(),
+            pending: VecDeque::new(),
             levels: HashMap::new(),
             max_x: 0,
             max_y: 0,
@@ -118,24 +142,37 @@ impl Stack {
     }
 
     fn settle_bricks(&mut self, input: Vec<String>) {
+        self.load_bricks(input);
+        while self.settle_next() {}
+    }
+
+    fn load_bricks(&mut self, input: Vec<String>) {
         let bricks = make_bricks(input);
         self.update_dimensions(&bricks);
-        for mut brick in bricks {
-            let z = brick.ends.0 .2;
-            if z == 1 {
-                self.update_levels(&brick, z);
-                self.bricks.insert(brick.id, brick);
-                continue;
-            }
-            let next_under = self.update_supported_by(&mut brick, z);
-            self.update_levels(&brick, next_under + 1);
-            self.bricks.insert(brick.id, brick.clone());
+        self.pending = bricks.into();
+    }
+
+    // let the lowest pending brick fall into place, returns false if none are left
+    fn settle_next(&mut self) -> bool {
+        let Some(mut brick) = self.pending.pop_front() else {
+            return false;
+        };
+        let z = brick.ends.0 .2;
+        if z == 1 {
+            self.update_levels(&brick, z);
+            self.bricks.insert(brick.id, brick);
+            return true;
         }
+        let next_under = self.update_supported_by(&mut brick, z);
+        self.update_levels(&brick, next_under + 1);
+        self.bricks.insert(brick.id, brick);
+        true
     }
 
     fn update_dimensions(&mut self, bricks: &Vec<Brick>) {
+        // ends are sorted, so the far end of a brick bounds the grid
         let (max_x, max_y) = bricks.iter().fold((0, 0), |acc, brick| {
-            (acc.0.max(brick.ends.0 .0), acc.1.max(brick.ends.0 .1))
+            (acc.0.max(brick.ends.1 .0), acc.1.max(brick.ends.1 .1))
         });
         self.max_x = max_x;
         self.max_y = max_y;
@@ -242,9 +279,51 @@ impl Stack {
     }
 }
 
+// one step settles a single brick, lowest first
+impl Simulation for Stack {
+    fn step(&mut self) -> bool {
+        self.settle_next() && !self.pending.is_empty()
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        let mut levels = self.levels.keys().copied().collect::<Vec<_>>();
+        levels.sort_unstable_by(|a, b| b.cmp(a));
+        for z in levels {
+            let mut ids = self.levels[&z]
+                .iter()
+                .flatten()
+                .filter(|id| **id != 0)
+                .collect::<Vec<_>>();
+            ids.sort_unstable();
+            ids.dedup();
+            let ids = ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+            out.push_str(&format!("z={}: {}\n", z, ids.join(" ")));
+        }
+        out.push_str(&format!(
+            "settled: {}, pending: {}\n",
+            self.bricks.len(),
+            self.pending.len()
+        ));
+        out
+    }
+
+    // supported conditions: brick=<id> (brick has settled), height=<z> (top of the stack)
+    fn breakpoint(&self, condition: &str) -> bool {
+        match condition.split_once('=') {
+            Some(("brick", value)) => value
+                .parse()
+                .is_ok_and(|id: usize| self.bricks.contains_key(&id)),
+            Some(("height", value)) => value.parse().ok() == self.levels.keys().max().copied(),
+            _ => false,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_bricks() {
@@ -328,4 +407,81 @@ mod tests {
         assert_eq!(stack.chain_reaction(6), 1);
         assert_eq!(stack.chain_reaction(7), 0);
     }
+
+    #[test]
+    fn test_grid_fits_far_ends() {
+        // both bricks only reach past x=0 and y=0 at their far end
+        let input = vec!["0,0,1~2,0,1".to_string(), "0,0,3~0,2,3".to_string()];
+        let mut stack = Stack::new();
+        stack.settle_bricks(input);
+        assert_eq!((stack.max_x, stack.max_y), (2, 2));
+        assert_eq!(stack.count_disintegrateable(), 1);
+        assert_eq!(stack.chain_reaction(1), 1);
+    }
+
+    #[test]
+    fn test_step_debugger() {
+        let input = read_lines("src/bin/day22/test_input.txt").unwrap();
+        let mut stack = Stack::new();
+        stack.load_bricks(input);
+        let commands = "break brick=4\ncontinue\nprint\nclear\ncontinue\n";
+        let mut output = Vec::new();
+        let stack = debug(stack, commands.as_bytes(), &mut output).unwrap();
+        assert_eq!(stack.bricks.len(), 7);
+        assert_eq!(stack.count_disintegrateable(), 5);
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(
+            output,
+            "breakpoint brick=4 at step 4\nz=3: 4\nz=2: 2 3\nz=1: 1\n\
+             settled: 4, pending: 3\nfinished at step 7\n"
+        );
+    }
+
+    // stack bricks on top of each other in the air so none of them overlap before falling
+    fn snapshot_strategy() -> impl Strategy<Value = Vec<String>> {
+        prop::collection::vec((0usize..4, 0usize..4, 0usize..3, 0usize..3), 1..12).prop_map(
+            |shapes| {
+                let mut z = 1;
+                shapes
+                    .into_iter()
+                    .map(|(x, y, axis, length)| {
+                        let end = match axis {
+                            0 => (x + length, y, z),
+                            1 => (x, y + length, z),
+                            _ => (x, y, z + length),
+                        };
+                        let line = format!("{},{},{}~{},{},{}", x, y, z, end.0, end.1, end.2);
+                        z = end.2 + 1 + length;
+                        line
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn prop_settled_bricks_do_not_float(input in snapshot_strategy()) {
+            let mut stack = Stack::new();
+            stack.settle_bricks(input);
+            for (id, brick) in &stack.bricks {
+                let (start, end) = &brick.ends;
+                let bottom = *stack
+                    .levels
+                    .iter()
+                    .filter(|(_, level)| level[start.0][start.1] == *id)
+                    .map(|(z, _)| z)
+                    .min()
+                    .unwrap();
+                prop_assert_eq!(bottom == 1, brick.supported_by.is_empty());
+                if bottom > 1 {
+                    let below = &stack.levels[&(bottom - 1)];
+                    let resting = (start.0..=end.0)
+                        .flat_map(|x| (start.1..=end.1).map(move |y| (x, y)))
+                        .any(|(x, y)| below[x][y] != 0);
+                    prop_assert!(resting);
+                }
+            }
+        }
+    }
 }
@@ -1,10 +1,16 @@
 // --- Day 23: A Long Walk ---
 
+use advent_of_code_2023::{fuzz, workspace::Puzzle};
 use std::collections::{HashMap, HashSet};
 
 fn main() {
+    if fuzz::from_args("day23", fuzz_target) {
+        return;
+    }
+
+    let input = Puzzle::from_env(2023, 23).input();
     let time_start = std::time::Instant::now();
-    let count = part_1("src/bin/day23/input.txt");
+    let count = part_1(&input);
     println!(
         "Part 1: {:?}  Time: {}μs",
         count,
@@ -12,7 +18,7 @@ fn main() {
     );
 
     let time_start = std::time::Instant::now();
-    let count = part_2("src/bin/day23/input.txt");
+    let count = part_2(&input);
     println!(
         "Part 2: {:?}  Time: {}μs",
         count,
@@ -20,6 +26,13 @@ fn main() {
     );
 }
 
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        part_1(filename);
+        part_2(filename);
+    });
+}
+
 fn part_1(filename: &str) -> u16 {
     let input = advent_of_code_2023::read_lines(filename).unwrap();
     let mut map = Map::new(input);
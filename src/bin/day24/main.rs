@@ -1,6 +1,13 @@
+use advent_of_code_2023::{fuzz, workspace::Puzzle};
+
 fn main() {
+    if fuzz::from_args("day24", fuzz_target) {
+        return;
+    }
+
+    let input = Puzzle::from_env(2023, 24).input();
     let time_start = std::time::Instant::now();
-    let count = part_1("src/bin/day24/input.txt");
+    let count = part_1(&input);
     println!(
         "Part 1: {:?}  Time: {}μs",
         count,
@@ -8,9 +15,14 @@ fn main() {
     );
 }
 
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        part_1(filename);
+    });
+}
+
 fn part_1(filename: &str) -> usize {
-    let input = advent_of_code_2023::read_lines(filename).unwrap();
-    let hailstones = make_hailstones(input);
+    let hailstones = read_hailstones(filename);
     let intersections = find_intersections(
         &hailstones,
         Bounds {
@@ -84,8 +96,8 @@ impl Hailstone {
     }
 }
 
-fn make_hailstones(input: Vec<String>) -> Vec<Hailstone> {
-    input.iter().map(|s| Hailstone::new(s)).collect()
+fn read_hailstones(filename: &str) -> Vec<Hailstone> {
+    advent_of_code_2023::map_lines(filename, Hailstone::new).unwrap()
 }
 
 fn find_intersections(hailstones: &Vec<Hailstone>, bounds: Bounds) -> Vec<(usize, usize)> {
@@ -116,7 +128,6 @@ fn find_intersections(hailstones: &Vec<Hailstone>, bounds: Bounds) -> Vec<(usize
 #[cfg(test)]
 mod tests {
     use super::*;
-    use advent_of_code_2023::read_lines;
 
     #[test]
     fn test_hailstones() {
@@ -124,7 +135,7 @@ mod tests {
             "19, 13, 30 @ -2,  1, -2".to_string(),
             "18, 19, 22 @ -1, -1, -2".to_string(),
         ];
-        let mut hailstones = make_hailstones(input);
+        let hailstones = input.iter().map(|s| Hailstone::new(s)).collect::<Vec<_>>();
         assert_eq!(hailstones.len(), 2);
         assert_eq!(
             hailstones[0],
@@ -158,8 +169,7 @@ mod tests {
 
     #[test]
     fn test_find_intersections() {
-        let input = read_lines("src/bin/day24/test_input.txt").unwrap();
-        let hailstones = make_hailstones(input);
+        let hailstones = read_hailstones("src/bin/day24/test_input.txt");
         let intersections = find_intersections(
             &hailstones,
             Bounds {
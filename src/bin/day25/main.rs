@@ -1,3 +1,4 @@
+use advent_of_code_2023::{fuzz, workspace::Puzzle};
 use rustworkx_core::{
     connectivity::stoer_wagner_min_cut,
     petgraph::graph::{NodeIndex, UnGraph},
@@ -6,14 +7,25 @@ use rustworkx_core::{
 use std::collections::HashMap;
 
 fn main() {
+    if fuzz::from_args("day25", fuzz_target) {
+        return;
+    }
+
+    let input = Puzzle::from_env(2023, 25).input();
     let time_start = std::time::Instant::now();
     println!(
-        "Min Cut: {:?}  Time: {}μs",
-        find_mul("src/bin/day25/input.txt"),
+        "Part 1: {:?}  Time: {}μs",
+        find_mul(&input),
         time_start.elapsed().as_micros()
     );
 }
 
+fn fuzz_target(data: &[u8]) {
+    fuzz::with_input_file(data, |filename| {
+        find_mul(filename);
+    });
+}
+
 fn find_mul(filename: &str) -> usize {
     let input = advent_of_code_2023::read_lines(filename).unwrap();
     let graph = make_graph(input);
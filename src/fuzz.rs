@@ -0,0 +1,405 @@
+// A small random-mutation fuzzer that runs offline. A target takes the raw input bytes, the
+// corpus is seeded from the example inputs of the day (plus anything in `fuzz/corpus/<day>/`),
+// and every input that panics or runs past the timeout is written to `fuzz/artifacts/<day>/`
+// as `crash-<hash>` or `timeout-<hash>`. Unlike libFuzzer there is no coverage feedback: every
+// input is a mutation of the seed corpus, which never grows. An artifact is replayed by passing
+// it back:
+//
+//   cargo run --bin day05 -- --fuzz 100000
+//   cargo run --bin day05 -- --fuzz fuzz/artifacts/day05/crash-1a2b3c4d5e6f7a8b
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Once};
+use std::thread;
+use std::time::Duration;
+
+pub type Target = fn(&[u8]);
+
+const DEFAULT_ITERATIONS: usize = 10_000;
+const TIMEOUT: Duration = Duration::from_secs(5);
+// mutated inputs are cut back to this many bytes
+const MAX_LEN: usize = 4096;
+// bytes that are meaningful to most of the puzzle parsers
+const INTERESTING: &[u8] = b"0123456789 \n,:;|=-~@#.()SJ<>{}%&";
+
+thread_local! {
+    // set on the threads that run a target, the panic hook only silences panics there
+    static FUZZING: Cell<bool> = const { Cell::new(false) };
+    // location of the last panic on this thread, recorded by the panic hook
+    static LAST_PANIC: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Pass,
+    Crash(String),
+    Timeout,
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    pub runs: usize,
+    pub crashes: Vec<(PathBuf, String)>,
+    pub timeout: Option<PathBuf>,
+}
+
+// handles `--fuzz [iterations | artifact]` for a day, returns false if the flag is not given
+pub fn from_args(day: &str, target: Target) -> bool {
+    let args = std::env::args().collect::<Vec<_>>();
+    let Some(idx) = args.iter().position(|arg| arg == "--fuzz") else {
+        return false;
+    };
+    match args.get(idx + 1) {
+        Some(arg) if Path::new(arg).is_file() => {
+            let data = fs::read(arg).unwrap();
+            println!("{}: {:?}", arg, run_one(target, &data, TIMEOUT));
+        }
+        arg => {
+            let iterations = arg
+                .and_then(|a| a.parse().ok())
+                .unwrap_or(DEFAULT_ITERATIONS);
+            let report = fuzz(day, target, iterations);
+            println!("{} runs", report.runs);
+            for (path, location) in &report.crashes {
+                println!("crash at {}: {}", location, path.display());
+            }
+            if let Some(path) = &report.timeout {
+                println!("timeout: {}", path.display());
+            }
+        }
+    }
+    true
+}
+
+// the solvers read their input from a file, so the fuzz input is written to a scratch file first
+pub fn with_input_file<F: FnOnce(&str)>(data: &[u8], f: F) {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let path = std::env::temp_dir().join(format!("aoc-fuzz-{}-{}.txt", std::process::id(), n));
+    fs::write(&path, data).unwrap();
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| f(path.to_str().unwrap())));
+    let _ = fs::remove_file(&path);
+    if let Err(payload) = result {
+        panic::resume_unwind(payload);
+    }
+}
+
+// The panic hook is global, so it is installed once and wraps the one that was there before:
+// panics on fuzzing threads are recorded quietly, any other thread (like a test running in
+// parallel) still gets the previous hook.
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !FUZZING.with(Cell::get) {
+                return previous_hook(info);
+            }
+            let location = info
+                .location()
+                .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()))
+                .unwrap_or_default();
+            LAST_PANIC.with(|last| *last.borrow_mut() = Some(location));
+        }));
+    });
+}
+
+// run the target once on its own thread so panics are caught and hangs can be timed out
+pub fn run_one(target: Target, data: &[u8], timeout: Duration) -> Outcome {
+    install_panic_hook();
+    let data = data.to_vec();
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        FUZZING.with(|fuzzing| fuzzing.set(true));
+        let result = panic::catch_unwind(|| target(&data));
+        let location = LAST_PANIC.with(|last| last.borrow_mut().take());
+        let _ = tx.send(
+            result
+                .err()
+                .map(|payload| location.unwrap_or_else(|| message_key(&*payload))),
+        );
+    });
+    match rx.recv_timeout(timeout) {
+        Ok(None) => Outcome::Pass,
+        Ok(Some(location)) => Outcome::Crash(location),
+        Err(_) => Outcome::Timeout,
+    }
+}
+
+// A panic that reaches the target's thread through `resume_unwind`, like one from a thread the
+// target joined, has no location recorded. It is told apart from other crashes by its message.
+fn message_key(payload: &(dyn Any + Send)) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_default();
+    format!("panic {:016x}", hash(message.as_bytes()))
+}
+
+pub fn fuzz(day: &str, target: Target, iterations: usize) -> Report {
+    let corpus = seed_corpus(
+        Path::new(&format!("src/bin/{}", day)),
+        Path::new(&format!("fuzz/corpus/{}", day)),
+    );
+    let artifacts = PathBuf::from("fuzz/artifacts").join(day);
+    let mut rng = StdRng::from_entropy();
+    let mut report = Report::default();
+    let mut seen = HashSet::new();
+
+    for i in 0..corpus.len() + iterations {
+        let data = match corpus.get(i) {
+            Some(seed) => seed.clone(),
+            None => mutate(&mut rng, &corpus),
+        };
+        report.runs += 1;
+        match run_one(target, &data, TIMEOUT) {
+            Outcome::Pass => (),
+            Outcome::Crash(location) => {
+                // keep one input per panic location
+                if seen.insert(location.clone()) {
+                    let path = save_artifact(&artifacts, "crash", &data);
+                    report.crashes.push((path, location));
+                }
+            }
+            Outcome::Timeout => {
+                // the hung thread cannot be stopped, so fuzzing ends here
+                report.timeout = Some(save_artifact(&artifacts, "timeout", &data));
+                break;
+            }
+        }
+    }
+    report
+}
+
+// the example inputs among the day's files, and every file of the extra corpus
+fn seed_corpus(examples: &Path, extra: &Path) -> Vec<Vec<u8>> {
+    let examples = fs::read_dir(examples)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with("test_input")
+        });
+    let extra = fs::read_dir(extra).into_iter().flatten().flatten();
+    let mut paths = examples
+        .chain(extra)
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+    // directory order is up to the file system, sorting keeps runs with the same seed the same
+    paths.sort();
+    let mut corpus = paths
+        .iter()
+        .filter_map(|path| fs::read(path).ok())
+        .collect::<Vec<_>>();
+    if corpus.is_empty() {
+        corpus.push(Vec::new());
+    }
+    corpus
+}
+
+fn mutate(rng: &mut StdRng, corpus: &[Vec<u8>]) -> Vec<u8> {
+    let mut data = corpus[rng.gen_range(0..corpus.len())].clone();
+    for _ in 0..rng.gen_range(1..=4) {
+        let len = data.len();
+        match rng.gen_range(0..7) {
+            // flip a bit
+            0 if len > 0 => data[rng.gen_range(0..len)] ^= 1 << rng.gen_range(0..8),
+            // overwrite with a byte the parsers care about
+            1 if len > 0 => {
+                data[rng.gen_range(0..len)] = INTERESTING[rng.gen_range(0..INTERESTING.len())]
+            }
+            // insert a random byte
+            2 => data.insert(rng.gen_range(0..=len), rng.gen()),
+            // delete a range
+            3 if len > 0 => {
+                let start = rng.gen_range(0..len);
+                let end = rng.gen_range(start..=len.min(start + 16));
+                data.drain(start..end);
+            }
+            // duplicate a range
+            4 if len > 0 => {
+                let start = rng.gen_range(0..len);
+                let end = rng.gen_range(start..=len.min(start + 64));
+                let chunk = data[start..end].to_vec();
+                let at = rng.gen_range(0..=len);
+                data.splice(at..at, chunk);
+            }
+            // truncate
+            5 => data.truncate(rng.gen_range(0..=len)),
+            // splice in the tail of another corpus entry
+            6 => {
+                let other = &corpus[rng.gen_range(0..corpus.len())];
+                let from = rng.gen_range(0..=other.len());
+                data.truncate(rng.gen_range(0..=len));
+                data.extend_from_slice(&other[from..]);
+            }
+            _ => (),
+        }
+    }
+    data.truncate(MAX_LEN);
+    data
+}
+
+fn hash(data: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    hasher.finish()
+}
+
+// the same input is always saved under the same name, so saving it again overwrites it
+fn save_artifact(dir: &Path, kind: &str, data: &[u8]) -> PathBuf {
+    let path = dir.join(format!("{}-{:016x}", kind, hash(data)));
+    fs::create_dir_all(dir).unwrap();
+    fs::write(&path, data).unwrap();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a fresh directory under the system temp dir, removed again by `remove_scratch`
+    fn scratch(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("aoc-fuzz-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn remove_scratch(dir: &Path) {
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    fn pass(_: &[u8]) {}
+
+    fn boom(_: &[u8]) {
+        panic!("boom");
+    }
+
+    fn sleepy(_: &[u8]) {
+        thread::sleep(Duration::from_millis(500));
+    }
+
+    // panics on another thread and passes the panic on like a joined thread would
+    fn relay(data: &[u8]) {
+        let message = String::from_utf8_lossy(data).into_owned();
+        let result = thread::spawn(move || panic!("{}", message)).join();
+        if let Err(payload) = result {
+            panic::resume_unwind(payload);
+        }
+    }
+
+    #[test]
+    fn test_mutate_is_deterministic_for_a_seed() {
+        let corpus = vec![
+            b"Time: 7 15 30\nDistance: 9 40 200\n".to_vec(),
+            b"32T3K 765\n".to_vec(),
+        ];
+        let run = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..100)
+                .map(|_| mutate(&mut rng, &corpus))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7), run(8));
+    }
+
+    #[test]
+    fn test_mutate_stays_within_max_len() {
+        let corpus = vec![vec![b'1'; MAX_LEN * 2], Vec::new()];
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..1000 {
+            assert!(mutate(&mut rng, &corpus).len() <= MAX_LEN);
+        }
+    }
+
+    #[test]
+    fn test_seed_corpus() {
+        let examples = scratch("examples");
+        let extra = scratch("extra");
+        fs::write(examples.join("test_input.txt"), "a").unwrap();
+        fs::write(examples.join("test_input2.txt"), "b").unwrap();
+        fs::write(examples.join("input.txt"), "puzzle").unwrap();
+        fs::write(examples.join("main.rs"), "fn main() {}").unwrap();
+        fs::write(extra.join("found"), "c").unwrap();
+
+        assert_eq!(
+            seed_corpus(&examples, &extra),
+            vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec()]
+        );
+        assert_eq!(
+            seed_corpus(&extra, &examples.join("missing")),
+            vec![Vec::<u8>::new()]
+        );
+        remove_scratch(&examples);
+        remove_scratch(&extra);
+    }
+
+    #[test]
+    fn test_run_one_passes() {
+        assert_eq!(run_one(pass, b"", TIMEOUT), Outcome::Pass);
+    }
+
+    #[test]
+    fn test_run_one_catches_a_panic_at_its_location() {
+        match run_one(boom, b"", TIMEOUT) {
+            Outcome::Crash(location) => {
+                assert!(location.starts_with("src/fuzz.rs:"), "{}", location)
+            }
+            outcome => panic!("expected a crash, got {:?}", outcome),
+        }
+    }
+
+    #[test]
+    fn test_run_one_keys_relayed_panics_by_message() {
+        let first = run_one(relay, b"first", TIMEOUT);
+        assert_eq!(run_one(relay, b"first", TIMEOUT), first);
+        assert_ne!(run_one(relay, b"second", TIMEOUT), first);
+        match first {
+            Outcome::Crash(key) => assert!(key.starts_with("panic ") && key.len() == 22, "{}", key),
+            outcome => panic!("expected a crash, got {:?}", outcome),
+        }
+    }
+
+    #[test]
+    fn test_run_one_times_out() {
+        assert_eq!(
+            run_one(sleepy, b"", Duration::from_millis(10)),
+            Outcome::Timeout
+        );
+    }
+
+    #[test]
+    fn test_save_artifact() {
+        let dir = scratch("artifacts");
+        let path = save_artifact(&dir, "crash", b"1 2 3");
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("crash-"));
+        assert_eq!(name.len(), 22);
+        assert!(name[6..].chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(fs::read(&path).unwrap(), b"1 2 3");
+
+        assert_eq!(save_artifact(&dir, "crash", b"1 2 3"), path);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        assert_ne!(save_artifact(&dir, "crash", b"1 2 4"), path);
+        assert_ne!(save_artifact(&dir, "timeout", b"1 2 3"), path);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+        remove_scratch(&dir);
+    }
+}
@@ -1,6 +1,11 @@
+pub mod bench;
+pub mod fuzz;
+pub mod workspace;
+
+use std::collections::VecDeque;
 use std::fs::File;
 use std::io;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Write};
 use std::path::Path;
 
 // read .txt file and return the lines as a vector of strings
@@ -13,6 +18,114 @@ where
     buf.lines().collect()
 }
 
+// streams a file record by record, each record is borrowed from a buffer that is reused for the
+// next one, so only the current line (or section) is held in memory
+pub struct LineReader<R> {
+    reader: R,
+    buf: Vec<u8>,
+}
+
+impl LineReader<BufReader<File>> {
+    pub fn open<P>(filename: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(LineReader::new(BufReader::new(File::open(filename)?)))
+    }
+}
+
+impl<R: BufRead> LineReader<R> {
+    pub fn new(reader: R) -> Self {
+        LineReader {
+            reader,
+            buf: Vec::new(),
+        }
+    }
+
+    // next line without its line ending, None at the end of the input
+    pub fn next_line(&mut self) -> io::Result<Option<&str>> {
+        self.next_record(b'\n')
+    }
+
+    // next record up to the delimiter, with the delimiter and any line ending trimmed
+    pub fn next_record(&mut self, delimiter: u8) -> io::Result<Option<&str>> {
+        self.buf.clear();
+        if self.reader.read_until(delimiter, &mut self.buf)? == 0 {
+            return Ok(None);
+        }
+        let record = self.buf.strip_suffix(&[delimiter]).unwrap_or(&self.buf);
+        to_str(record).map(Some)
+    }
+
+    // next block of lines up to a blank line, joined by '\n' and ready for `str::lines`
+    pub fn next_section(&mut self) -> io::Result<Option<&str>> {
+        self.buf.clear();
+        loop {
+            let start = self.buf.len();
+            if self.reader.read_until(b'\n', &mut self.buf)? == 0 {
+                break;
+            }
+            if self.buf[start..].trim_ascii().is_empty() {
+                self.buf.truncate(start);
+                if start > 0 {
+                    break;
+                }
+            }
+        }
+        if self.buf.is_empty() {
+            return Ok(None);
+        }
+        to_str(&self.buf).map(Some)
+    }
+
+    // up to `max_lines` lines joined by '\n', for handing batches of a large input to a solver
+    pub fn next_chunk(&mut self, max_lines: usize) -> io::Result<Option<&str>> {
+        self.buf.clear();
+        for _ in 0..max_lines {
+            if self.reader.read_until(b'\n', &mut self.buf)? == 0 {
+                break;
+            }
+        }
+        if self.buf.is_empty() {
+            return Ok(None);
+        }
+        to_str(&self.buf).map(Some)
+    }
+}
+
+// borrow the bytes as a str without trailing line endings
+fn to_str(bytes: &[u8]) -> io::Result<&str> {
+    std::str::from_utf8(bytes)
+        .map(|s| s.trim_end_matches(['\r', '\n']))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+// fold over the lines of a file without collecting them
+pub fn fold_lines<P, T, F>(filename: P, init: T, mut f: F) -> io::Result<T>
+where
+    P: AsRef<Path>,
+    F: FnMut(T, &str) -> T,
+{
+    let mut reader = LineReader::open(filename)?;
+    let mut acc = init;
+    while let Some(line) = reader.next_line()? {
+        acc = f(acc, line);
+    }
+    Ok(acc)
+}
+
+// parse every line of a file into a value, keeping only the parsed values
+pub fn map_lines<P, T, F>(filename: P, mut f: F) -> io::Result<Vec<T>>
+where
+    P: AsRef<Path>,
+    F: FnMut(&str) -> T,
+{
+    fold_lines(filename, Vec::new(), |mut values, line| {
+        values.push(f(line));
+        values
+    })
+}
+
 pub fn gcd(mut a: i64, mut b: i64) -> i64 {
     while b != 0 {
         let t = b;
@@ -25,3 +138,296 @@ pub fn gcd(mut a: i64, mut b: i64) -> i64 {
 pub fn lcm(a: i64, b: i64) -> i64 {
     a * b / gcd(a, b)
 }
+
+/// A simulation that can be advanced one step at a time by the step debugger.
+pub trait Simulation: Clone {
+    /// Advances the simulation by one step, returns false once there is nothing left to do.
+    fn step(&mut self) -> bool;
+
+    /// Renders the current state for inspection.
+    fn render(&self) -> String;
+
+    /// Checks a breakpoint condition like "load=64" against the current state.
+    fn breakpoint(&self, condition: &str) -> bool;
+}
+
+// some simulations never end by themselves, so `continue` gives up after this many steps
+pub const MAX_CONTINUE_STEPS: usize = 1_000_000;
+// older snapshots are dropped, so long runs don't keep every state they went through
+pub const MAX_SNAPSHOTS: usize = 1_000;
+
+// keeps the state before a step, dropping the oldest one when there are MAX_SNAPSHOTS already
+fn record<S: Clone>(snapshots: &mut VecDeque<S>, current: &S) {
+    if snapshots.len() == MAX_SNAPSHOTS {
+        snapshots.pop_front();
+    }
+    snapshots.push_back(current.clone());
+}
+
+// interactive step debugger, reads commands from input and writes the state to output:
+//   step [n]      advance n steps (default 1)
+//   continue [n]  run until a breakpoint hits or the simulation ends, at most n steps
+//                 (default MAX_CONTINUE_STEPS)
+//   break <cond>  add a breakpoint, `break` alone lists them
+//   clear         remove all breakpoints
+//   rewind [n]    go back n steps using the recorded snapshots, the last MAX_SNAPSHOTS are kept
+//   print         render the current state
+//   quit          stop debugging
+pub fn debug<S, R, W>(simulation: S, input: R, mut output: W) -> io::Result<S>
+where
+    S: Simulation,
+    R: BufRead,
+    W: Write,
+{
+    let mut current = simulation;
+    let mut snapshots: VecDeque<S> = VecDeque::new();
+    let mut steps = 0;
+    let mut breakpoints: Vec<String> = Vec::new();
+    let mut finished = false;
+
+    for line in input.lines() {
+        let line = line?;
+        let mut words = line.split_whitespace();
+        let Some(command) = words.next() else {
+            continue;
+        };
+        let argument = words.collect::<Vec<_>>().join(" ");
+        let count = argument.parse::<usize>().ok();
+        match command {
+            "s" | "step" => {
+                for _ in 0..count.unwrap_or(1) {
+                    if finished {
+                        break;
+                    }
+                    record(&mut snapshots, &current);
+                    steps += 1;
+                    finished = !current.step();
+                }
+                writeln!(output, "step {}", steps)?;
+            }
+            "c" | "continue" => {
+                let mut hit = None;
+                let mut left = count.unwrap_or(MAX_CONTINUE_STEPS);
+                while !finished && hit.is_none() && left > 0 {
+                    record(&mut snapshots, &current);
+                    steps += 1;
+                    finished = !current.step();
+                    hit = breakpoints.iter().find(|b| current.breakpoint(b));
+                    left -= 1;
+                }
+                match hit {
+                    Some(condition) => {
+                        writeln!(output, "breakpoint {} at step {}", condition, steps)?
+                    }
+                    None if finished => writeln!(output, "finished at step {}", steps)?,
+                    None => writeln!(output, "stopped at step {}", steps)?,
+                }
+            }
+            "b" | "break" => {
+                if argument.is_empty() {
+                    for (i, condition) in breakpoints.iter().enumerate() {
+                        writeln!(output, "{}: {}", i, condition)?;
+                    }
+                } else {
+                    breakpoints.push(argument);
+                }
+            }
+            "clear" => breakpoints.clear(),
+            "r" | "rewind" => {
+                for _ in 0..count.unwrap_or(1) {
+                    if let Some(previous) = snapshots.pop_back() {
+                        current = previous;
+                        steps -= 1;
+                        finished = false;
+                    }
+                }
+                writeln!(output, "step {}", steps)?;
+            }
+            "p" | "print" => write!(output, "{}", current.render())?,
+            "q" | "quit" => break,
+            _ => writeln!(output, "unknown command: {}", command)?,
+        }
+    }
+
+    Ok(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn reader(input: &[u8]) -> LineReader<Cursor<&[u8]>> {
+        LineReader::new(Cursor::new(input))
+    }
+
+    #[test]
+    fn test_next_line() {
+        // CRLF endings, and no newline after the last line
+        let mut lines = reader(b"a\r\n\r\nb\nc");
+        assert_eq!(lines.next_line().unwrap(), Some("a"));
+        assert_eq!(lines.next_line().unwrap(), Some(""));
+        assert_eq!(lines.next_line().unwrap(), Some("b"));
+        assert_eq!(lines.next_line().unwrap(), Some("c"));
+        assert_eq!(lines.next_line().unwrap(), None);
+        assert_eq!(reader(b"").next_line().unwrap(), None);
+    }
+
+    #[test]
+    fn test_next_record() {
+        let mut records = reader(b"rn=1,cm-\r\n,,qp=3\n");
+        assert_eq!(records.next_record(b',').unwrap(), Some("rn=1"));
+        assert_eq!(records.next_record(b',').unwrap(), Some("cm-"));
+        assert_eq!(records.next_record(b',').unwrap(), Some(""));
+        assert_eq!(records.next_record(b',').unwrap(), Some("qp=3"));
+        assert_eq!(records.next_record(b',').unwrap(), None);
+    }
+
+    #[test]
+    fn test_next_section() {
+        // leading blank lines and runs of blank lines, some of them only whitespace, are skipped
+        let mut sections = reader(b"\n\r\na\r\nb\r\n\n  \n\nc\n\n\nd");
+        let section = sections.next_section().unwrap().unwrap();
+        assert_eq!(section, "a\r\nb");
+        assert_eq!(section.lines().collect::<Vec<_>>(), vec!["a", "b"]);
+        assert_eq!(sections.next_section().unwrap(), Some("c"));
+        assert_eq!(sections.next_section().unwrap(), Some("d"));
+        assert_eq!(sections.next_section().unwrap(), None);
+        assert_eq!(reader(b"\n\n").next_section().unwrap(), None);
+    }
+
+    #[test]
+    fn test_next_chunk() {
+        let mut chunks = reader(b"1\n2\n3\n4\n");
+        assert_eq!(chunks.next_chunk(2).unwrap(), Some("1\n2"));
+        assert_eq!(chunks.next_chunk(2).unwrap(), Some("3\n4"));
+        assert_eq!(chunks.next_chunk(2).unwrap(), None);
+        let mut chunks = reader(b"1\r\n2\r\n3");
+        assert_eq!(chunks.next_chunk(3).unwrap(), Some("1\r\n2\r\n3"));
+        assert_eq!(chunks.next_chunk(3).unwrap(), None);
+        let mut chunks = reader(b"1\n2\n3\n");
+        assert_eq!(chunks.next_chunk(2).unwrap(), Some("1\n2"));
+        assert_eq!(chunks.next_chunk(2).unwrap(), Some("3"));
+        assert_eq!(chunks.next_chunk(2).unwrap(), None);
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        // the record is reported as an error, reading goes on with the next one
+        let mut lines = reader(b"ok\n\xff\xfe\nafter\n");
+        assert_eq!(lines.next_line().unwrap(), Some("ok"));
+        let error = lines.next_line().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(lines.next_line().unwrap(), Some("after"));
+        let error = reader(b"a\n\xff\n\nb").next_section().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let error = reader(b"a\n\xff").next_chunk(2).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_fold_and_map_lines() {
+        fuzz::with_input_file(b"1\r\n2\n3", |filename| {
+            assert_eq!(
+                fold_lines(filename, 0, |sum, line| sum + line.len()).unwrap(),
+                3
+            );
+            let values = map_lines(filename, |line| line.parse::<u32>().unwrap()).unwrap();
+            assert_eq!(values, vec![1, 2, 3]);
+        });
+        fuzz::with_input_file(b"\xff\n", |filename| {
+            let error = map_lines(filename, |line| line.len()).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        });
+        let error = fold_lines("no/such/file.txt", (), |_, _| ()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    }
+
+    // counts up by one per step and ends at `limit`, with breakpoints like "n=3"
+    #[derive(Clone)]
+    struct Counter {
+        n: usize,
+        limit: usize,
+    }
+
+    impl Simulation for Counter {
+        fn step(&mut self) -> bool {
+            self.n += 1;
+            self.n < self.limit
+        }
+
+        fn render(&self) -> String {
+            format!("n={}\n", self.n)
+        }
+
+        fn breakpoint(&self, condition: &str) -> bool {
+            condition.strip_prefix("n=") == Some(&self.n.to_string())
+        }
+    }
+
+    fn run_debug(limit: usize, commands: &str) -> (usize, String) {
+        let mut output = Vec::new();
+        let counter = Counter { n: 0, limit };
+        let counter = debug(counter, commands.as_bytes(), &mut output).unwrap();
+        (counter.n, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_debug() {
+        let commands = "step\n\
+                        s 2\n\
+                        print\n\
+                        break n=10\n\
+                        break\n\
+                        continue\n\
+                        rewind 3\n\
+                        p\n\
+                        clear\n\
+                        continue 5\n\
+                        continue\n\
+                        step\n\
+                        jump\n\
+                        quit\n\
+                        step\n";
+        let (n, output) = run_debug(20, commands);
+        assert_eq!(n, 20);
+        assert_eq!(
+            output.lines().collect::<Vec<_>>(),
+            vec![
+                "step 1",
+                "step 3",
+                "n=3",
+                "0: n=10",
+                "breakpoint n=10 at step 10",
+                "step 7",
+                "n=7",
+                "stopped at step 12",
+                "finished at step 20",
+                "step 20",
+                "unknown command: jump",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_debug_limits() {
+        // a simulation that never ends stops after MAX_CONTINUE_STEPS
+        let (n, output) = run_debug(usize::MAX, "continue\n");
+        assert_eq!(n, MAX_CONTINUE_STEPS);
+        assert_eq!(output, format!("stopped at step {}\n", MAX_CONTINUE_STEPS));
+
+        // only the last MAX_SNAPSHOTS steps can be rewound
+        let commands = format!(
+            "step {}\nrewind {}\n",
+            MAX_SNAPSHOTS + 5,
+            MAX_SNAPSHOTS + 100
+        );
+        let (n, output) = run_debug(usize::MAX, &commands);
+        assert_eq!(n, 5);
+        assert!(output.ends_with("step 5\n"));
+
+        // rewinding past the start stays at step 0
+        let (n, output) = run_debug(usize::MAX, "step 2\nrewind 5\n");
+        assert_eq!((n, output.as_str()), (0, "step 2\nstep 0\n"));
+    }
+}
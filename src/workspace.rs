@@ -0,0 +1,209 @@
+// Inputs and answers are stored per (year, day, profile) so several years and several people can
+// share the repo:
+//
+//   inputs/<year>/<profile>/dayNN/input.txt
+//   inputs/<year>/<profile>/dayNN/answers.txt
+//
+// A day picks its profile from `--profile <name>` or the `AOC_PROFILE` variable. The default
+// profile of 2023 falls back to the old `src/bin/dayNN/input.txt` location. Both are relative to
+// the current directory unless the puzzle is given another root.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_PROFILE: &str = "default";
+pub const PROFILE_VAR: &str = "AOC_PROFILE";
+const INPUTS_DIR: &str = "inputs";
+
+// every puzzle the runner knows about, with the binary that solves it
+pub struct Entry {
+    pub year: u16,
+    pub day: u8,
+    pub binary: &'static str,
+}
+
+macro_rules! year_2023 {
+    ($($day:literal => $binary:literal),* $(,)?) => {
+        &[$(Entry { year: 2023, day: $day, binary: $binary }),*]
+    };
+}
+
+pub static PUZZLES: &[Entry] = year_2023! {
+    1 => "day01", 2 => "day02", 3 => "day03", 4 => "day04", 5 => "day05",
+    6 => "day06", 7 => "day07", 8 => "day08", 9 => "day09", 10 => "day10",
+    11 => "day11", 12 => "day12", 13 => "day13", 14 => "day14", 15 => "day15",
+    16 => "day16", 17 => "day17", 18 => "day18", 19 => "day19", 20 => "day20",
+    21 => "day21", 22 => "day22", 23 => "day23", 24 => "day24", 25 => "day25",
+};
+
+pub fn find(year: u16, day: u8) -> Option<&'static Entry> {
+    PUZZLES.iter().find(|e| e.year == year && e.day == day)
+}
+
+pub fn days(year: u16) -> impl Iterator<Item = &'static Entry> {
+    PUZZLES.iter().filter(move |e| e.year == year)
+}
+
+// the profile comes from `--profile <name>`, then `AOC_PROFILE`, then the default
+pub fn profile() -> String {
+    let args = std::env::args().collect::<Vec<_>>();
+    args.iter()
+        .position(|arg| arg == "--profile")
+        .and_then(|idx| args.get(idx + 1).cloned())
+        .or_else(|| std::env::var(PROFILE_VAR).ok())
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Puzzle {
+    pub year: u16,
+    pub day: u8,
+    pub profile: String,
+    pub root: PathBuf,
+}
+
+impl Puzzle {
+    pub fn new(year: u16, day: u8, profile: &str) -> Self {
+        Puzzle {
+            year,
+            day,
+            profile: profile.to_string(),
+            root: PathBuf::new(),
+        }
+    }
+
+    // look for `inputs/` and `src/bin/` under another directory than the current one
+    pub fn with_root<P: AsRef<Path>>(mut self, root: P) -> Self {
+        self.root = root.as_ref().to_path_buf();
+        self
+    }
+
+    pub fn from_env(year: u16, day: u8) -> Self {
+        Puzzle::new(year, day, &profile())
+    }
+
+    pub fn dir(&self) -> PathBuf {
+        self.root
+            .join(INPUTS_DIR)
+            .join(self.year.to_string())
+            .join(&self.profile)
+            .join(format!("day{:02}", self.day))
+    }
+
+    pub fn input(&self) -> String {
+        let path = self.dir().join("input.txt");
+        let legacy = self
+            .root
+            .join(format!("src/bin/day{:02}/input.txt", self.day));
+        if !path.exists() && self.year == 2023 && self.profile == DEFAULT_PROFILE {
+            return legacy.to_string_lossy().into_owned();
+        }
+        path.to_string_lossy().into_owned()
+    }
+
+    pub fn answers_path(&self) -> PathBuf {
+        self.dir().join("answers.txt")
+    }
+
+    // stored answers, one `Part N: value` line per part, a missing file means none are known yet
+    pub fn answers(&self) -> io::Result<BTreeMap<u8, String>> {
+        match fs::read_to_string(self.answers_path()) {
+            Ok(content) => Ok(parse_answers(&content)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save_answers(&self, answers: &BTreeMap<u8, String>) -> io::Result<()> {
+        fs::create_dir_all(self.dir())?;
+        let content = answers
+            .iter()
+            .map(|(part, value)| format!("Part {}: {}\n", part, value))
+            .collect::<String>();
+        fs::write(self.answers_path(), content)
+    }
+}
+
+// picks the `Part N: value` lines out of a day's output, the value is the first word after the
+// colon so trailing timings are ignored
+pub fn parse_answers(output: &str) -> BTreeMap<u8, String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (label, rest) = line.split_once(':')?;
+            let part = label.trim().to_lowercase();
+            let part = part.strip_prefix("part")?.trim().parse().ok()?;
+            let value = rest.split_whitespace().next()?.trim_end_matches(',');
+            Some((part, value.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a fresh directory under the system temp dir for a test to store puzzles in
+    fn scratch_root(name: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("aoc-workspace-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[test]
+    fn test_parse_answers() {
+        let output = "Part 1: 6440\n\
+                      Time: 12μs\n\
+                      part 2 : 5905, took 3ms\n\
+                      Parts: 3\n\
+                      Part two: 4\n\
+                      Part 3:\n";
+        let answers = parse_answers(output);
+        assert_eq!(
+            answers,
+            BTreeMap::from([(1, "6440".to_string()), (2, "5905".to_string())])
+        );
+        assert!(parse_answers("").is_empty());
+    }
+
+    #[test]
+    fn test_input_paths() {
+        let puzzle = Puzzle::new(2023, 26, DEFAULT_PROFILE);
+        assert_eq!(puzzle.input(), "src/bin/day26/input.txt");
+
+        let root = scratch_root("inputs");
+        let puzzle = puzzle.with_root(&root);
+        let path = |p: &str| root.join(p).to_string_lossy().into_owned();
+        assert_eq!(puzzle.input(), path("src/bin/day26/input.txt"));
+        let other = Puzzle::new(2023, 26, "other").with_root(&root);
+        assert_eq!(other.input(), path("inputs/2023/other/day26/input.txt"));
+        let year = Puzzle::new(2024, 26, DEFAULT_PROFILE).with_root(&root);
+        assert_eq!(year.input(), path("inputs/2024/default/day26/input.txt"));
+
+        // once the input is stored by year and profile it is used instead of the old location
+        fs::create_dir_all(puzzle.dir()).unwrap();
+        fs::write(puzzle.dir().join("input.txt"), "").unwrap();
+        assert_eq!(puzzle.input(), path("inputs/2023/default/day26/input.txt"));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_answers_round_trip() {
+        let root = scratch_root("answers");
+        let puzzle = Puzzle::new(2023, 26, "test").with_root(&root);
+        assert_eq!(puzzle.answers().unwrap(), BTreeMap::new());
+
+        let answers = BTreeMap::from([(1, "6440".to_string()), (2, "5905".to_string())]);
+        puzzle.save_answers(&answers).unwrap();
+        assert_eq!(
+            fs::read_to_string(puzzle.answers_path()).unwrap(),
+            "Part 1: 6440\nPart 2: 5905\n"
+        );
+        assert_eq!(puzzle.answers().unwrap(), answers);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}